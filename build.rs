@@ -0,0 +1,5 @@
+fn main() {
+    // Sets the linker flags a Node addon needs, e.g. leaving the N-API symbols for node to resolve on macOS.
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
@@ -0,0 +1,68 @@
+/* C API for wasm-license-decoder, built with the `ffi` feature. Regenerate from src/ffi.rs with cbindgen (see cbindgen.toml). */
+
+#ifndef LICENSE_DECODER_H
+#define LICENSE_DECODER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The call succeeded and `out_json` holds the parsed data.
+ */
+#define LDC_OK 0
+
+/**
+ * Decoding failed and `out_json` holds `{ code, message, section?, field?, offset?, rsa_block? }`.
+ */
+#define LDC_DECODE_ERROR 1
+
+/**
+ * A required pointer was null. `out_json` is left untouched.
+ */
+#define LDC_INVALID_ARGUMENT 2
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parses a 720-byte driver's licence payload into a JSON string.
+ *
+ * # Safety
+ * `ptr` must point to `len` readable bytes and `out_json` must be a valid pointer to write to.
+ * The string written to `out_json` must be released with `ldc_free_string`.
+ */
+int32_t ldc_parse_drivers_license(const uint8_t *ptr, size_t len, char **out_json);
+
+/**
+ * Parses a vehicle licence disc payload into a JSON string.
+ *
+ * # Safety
+ * Same contract as `ldc_parse_drivers_license`.
+ */
+int32_t ldc_parse_vehicle_license(const uint8_t *ptr, size_t len, char **out_json);
+
+/**
+ * Detects the payload type and parses it into `{ "type": "drivers" | "vehicle" | "smart_id", "data": ... }`.
+ *
+ * # Safety
+ * Same contract as `ldc_parse_drivers_license`.
+ */
+int32_t ldc_parse_license(const uint8_t *ptr, size_t len, char **out_json);
+
+/**
+ * Releases a string returned through `out_json`. Null is ignored.
+ *
+ * # Safety
+ * `json` must be null or a string from this library that hasn't been freed yet.
+ */
+void ldc_free_string(char *json);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LICENSE_DECODER_H */
@@ -0,0 +1,130 @@
+use serde::Serialize;
+use crate::date::{DateValue, LicenseDate};
+use crate::drivers_license::DriversLicenseData;
+use crate::gender::Gender;
+
+const SOUTH_AFRICA_ALPHA_3: &str = "ZAF";
+// AAMVA's value for "no restrictions" or "no endorsements".
+const NONE: &str = "NONE";
+
+/// A licence as AAMVA DL/ID data elements, for software built around North American licence scanners. Keys are the
+/// element IDs. Dates are MMDDCCYY, as on US cards. Elements a licence doesn't carry, such as the jurisdiction code,
+/// address, height and eye colour, are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct AamvaElements {
+    /// Vehicle class: the licence codes, e.g. "B,EB".
+    #[serde(rename = "DCA")]
+    pub vehicle_class: String,
+    /// Restriction codes: the driver restriction codes, e.g. "1", or "NONE".
+    #[serde(rename = "DCB")]
+    pub restriction_codes: String,
+    /// Endorsement codes: the professional driving permit categories, e.g. "GP", or "NONE".
+    #[serde(rename = "DCD")]
+    pub endorsement_codes: String,
+    #[serde(rename = "DBA", skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<String>,
+    #[serde(rename = "DCS")]
+    pub family_name: String,
+    /// The first initial, as licences don't carry forenames.
+    #[serde(rename = "DAC")]
+    pub first_name: String,
+    /// The remaining initials.
+    #[serde(rename = "DAD")]
+    pub middle_name: String,
+    #[serde(rename = "DBD", skip_serializing_if = "Option::is_none")]
+    pub issue_date: Option<String>,
+    #[serde(rename = "DBB", skip_serializing_if = "Option::is_none")]
+    pub date_of_birth: Option<String>,
+    /// "1" for male, "2" for female or "9" when unknown.
+    #[serde(rename = "DBC")]
+    pub sex: String,
+    /// Customer ID number: the licence number.
+    #[serde(rename = "DAQ")]
+    pub customer_id: String,
+    /// Document discriminator: the licence issue number.
+    #[serde(rename = "DCF")]
+    pub document_discriminator: String,
+    #[serde(rename = "DCG")]
+    pub country: String,
+    /// Family name truncation: "N".
+    #[serde(rename = "DDE")]
+    pub family_name_truncation: String,
+    /// First name truncation: "T" when there's an initial, since it stands for a truncated name.
+    #[serde(rename = "DDF")]
+    pub first_name_truncation: String,
+    /// Middle name truncation, like `first_name_truncation`.
+    #[serde(rename = "DDG")]
+    pub middle_name_truncation: String,
+}
+
+impl AamvaElements {
+    /// The elements as `(id, value)` pairs in AAMVA order, leaving out absent ones.
+    pub fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("DCA", Some(&self.vehicle_class)),
+            ("DCB", Some(&self.restriction_codes)),
+            ("DCD", Some(&self.endorsement_codes)),
+            ("DBA", self.expiration_date.as_ref()),
+            ("DCS", Some(&self.family_name)),
+            ("DAC", Some(&self.first_name)),
+            ("DAD", Some(&self.middle_name)),
+            ("DBD", self.issue_date.as_ref()),
+            ("DBB", self.date_of_birth.as_ref()),
+            ("DBC", Some(&self.sex)),
+            ("DAQ", Some(&self.customer_id)),
+            ("DCF", Some(&self.document_discriminator)),
+            ("DCG", Some(&self.country)),
+            ("DDE", Some(&self.family_name_truncation)),
+            ("DDF", Some(&self.first_name_truncation)),
+            ("DDG", Some(&self.middle_name_truncation)),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| value.map(|value| (id, value.as_str())))
+        .collect()
+    }
+
+    /// The body of an AAMVA "DL" subfile: "DL", then each element ID and value separated by line feeds, ending in a
+    /// carriage return. There is no file header, as SA licences have no AAMVA issuer ID.
+    pub fn to_subfile(&self) -> String {
+        let elements: Vec<String> = self.pairs().into_iter().map(|(id, value)| format!("{}{}", id, value)).collect();
+        format!("DL{}\r", elements.join("\n"))
+    }
+}
+
+/// Maps a parsed licence to AAMVA data elements.
+pub fn to_aamva(license: &DriversLicenseData) -> AamvaElements {
+    let first_name = license.initials_list.first().cloned().unwrap_or_default();
+    let middle_name = license.initials_list.get(1..).unwrap_or_default().join(" ");
+    let truncation = |name: &str| if name.is_empty() { "N" } else { "T" }.to_string();
+    let restriction_codes: Vec<String> = license.driver_restrictions.iter().map(|restriction| restriction.code().to_string()).collect();
+    let endorsement_codes = license.pr_dp_code.as_deref().map(str::trim).filter(|code| !code.is_empty());
+
+    AamvaElements {
+        vehicle_class: license.vehicle_codes.join(","),
+        restriction_codes: if restriction_codes.is_empty() { NONE.to_string() } else { restriction_codes.join(",") },
+        endorsement_codes: endorsement_codes.unwrap_or(NONE).to_string(),
+        expiration_date: aamva_date(&license.license_expiry_date),
+        family_name: license.surname.trim().to_string(),
+        first_name_truncation: truncation(&first_name),
+        middle_name_truncation: truncation(&middle_name),
+        first_name,
+        middle_name,
+        issue_date: aamva_date(&license.license_issue_date),
+        date_of_birth: aamva_date(&license.birthdate),
+        sex: match license.gender.gender() {
+            Gender::Male => "1",
+            Gender::Female => "2",
+            Gender::Unknown => "9",
+        }
+        .to_string(),
+        customer_id: license.license_number.trim().to_string(),
+        document_discriminator: license.license_issue_number.trim().to_string(),
+        country: SOUTH_AFRICA_ALPHA_3.to_string(),
+        family_name_truncation: "N".to_string(),
+    }
+}
+
+fn aamva_date(value: &DateValue) -> Option<String> {
+    value.as_date().filter(LicenseDate::is_plausible).map(|date| format!("{:02}{:02}{:04}", date.month, date.day, date.year))
+}
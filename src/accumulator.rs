@@ -0,0 +1,217 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::drivers_license::{self, DriversLicenseData, ParseOptions};
+use crate::error::DecodeError;
+
+const LICENSE_LENGTH: usize = 720;
+
+/// Merges repeated scans of one damaged card into a best-effort parse.
+///
+/// A misread byte garbles its whole RSA block once decrypted, so scans are first merged byte by byte by majority
+/// vote. The merged payload and each scan are then parsed leniently, and every field takes its most common value.
+#[derive(Debug, Default, Clone)]
+pub struct ScanAccumulator {
+    scans: Vec<Vec<u8>>,
+    options: ParseOptions,
+}
+
+/// The merged licence and how strongly the scans agreed on it.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct AccumulatedScan {
+    pub data: DriversLicenseData,
+    /// The average share of scans that agreed with each merged field, from 0 to 1.
+    pub confidence: f64,
+    pub scans: usize,
+}
+
+impl ScanAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options applied to every parse. Parsing is always lenient, so damaged scans still contribute fields.
+    pub fn with_options(options: ParseOptions) -> Self {
+        ScanAccumulator { scans: Vec::new(), options }
+    }
+
+    pub fn add(&mut self, bytes: &[u8]) {
+        self.scans.push(bytes.to_vec());
+    }
+
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    /// The most common byte at each position across the full-length scans. None without any.
+    pub fn merged_bytes(&self) -> Option<Vec<u8>> {
+        let scans: Vec<&Vec<u8>> = self.scans.iter().filter(|scan| scan.len() == LICENSE_LENGTH).collect();
+        (0..LICENSE_LENGTH).map(|i| most_common(scans.iter().map(|scan| scan[i])).map(|(byte, _)| byte)).collect()
+    }
+
+    /// Fails only when neither the merged payload nor any single scan parses, with the merged payload's error.
+    pub fn result(&self) -> Result<AccumulatedScan, DecodeError> {
+        let options = ParseOptions { lenient: true, ..self.options.clone() };
+        let parse = |bytes: &[u8]| drivers_license::parse_bytes_with_options(bytes, options.clone()).and_then(|data| to_value(&data));
+
+        let merged = self.merged_bytes().ok_or(DecodeError::InsufficientBytes).and_then(|bytes| parse(&bytes));
+        let scans: Vec<Value> = self.scans.iter().filter_map(|scan| parse(scan).ok()).collect();
+
+        let merged = match merged {
+            Ok(merged) => Some(merged),
+            Err(e) if scans.is_empty() => return Err(e),
+            Err(_) => None,
+        };
+
+        let (fields, confidence) = merge_fields(merged.as_ref(), &scans, self.scans.len());
+        let data = serde_json::from_value(Value::Object(fields)).map_err(|e| DecodeError::SerializationFailed(e.to_string()))?;
+        Ok(AccumulatedScan { data, confidence, scans: self.scans.len() })
+    }
+}
+
+fn to_value(data: &DriversLicenseData) -> Result<Value, DecodeError> {
+    serde_json::to_value(data).map_err(|e| DecodeError::SerializationFailed(e.to_string()))
+}
+
+// Takes the most common value of every field across the merged payload and the single scans, with the merged
+// payload winning ties. Confidence is the average share of all scans whose own parse agreed with each field.
+fn merge_fields(merged: Option<&Value>, scans: &[Value], scan_count: usize) -> (Map<String, Value>, f64) {
+    let parses: Vec<&Value> = merged.into_iter().chain(scans).collect();
+
+    let mut keys: Vec<&String> = Vec::new();
+    for key in parses.iter().filter_map(|parse| parse.as_object()).flat_map(Map::keys) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    // Fields skipped when empty are missing rather than null, and stay missing if that wins the vote.
+    let mut fields = Map::new();
+    let mut agreement = 0.0;
+    for key in &keys {
+        if let Some((value, _)) = most_common(parses.iter().map(|parse| parse.get(key.as_str()))) {
+            let votes = scans.iter().filter(|scan| scan.get(key.as_str()) == value).count();
+            agreement += votes as f64 / scan_count.max(1) as f64;
+            if let Some(value) = value {
+                fields.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    let confidence = if keys.is_empty() { 0.0 } else { agreement / keys.len() as f64 };
+    (fields, confidence)
+}
+
+// The most frequent item and its count, or None without any items. Ties go to the item seen first.
+fn most_common<T: PartialEq>(items: impl Iterator<Item = T>) -> Option<(T, usize)> {
+    let mut counts: Vec<(T, usize)> = Vec::new();
+    for item in items {
+        match counts.iter_mut().find(|(seen, _)| *seen == item) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((item, 1)),
+        }
+    }
+    let best = counts.iter().map(|(_, count)| *count).max()?;
+    let index = counts.iter().position(|(_, count)| *count == best)?;
+    Some(counts.swap_remove(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scan(fill: u8) -> Vec<u8> {
+        vec![fill; LICENSE_LENGTH]
+    }
+
+    fn accumulator(scans: &[Vec<u8>]) -> ScanAccumulator {
+        let mut accumulator = ScanAccumulator::new();
+        for scan in scans {
+            accumulator.add(scan);
+        }
+        accumulator
+    }
+
+    #[test]
+    fn agreeing_scans_merge_to_the_same_bytes() {
+        let merged = accumulator(&[scan(0x42), scan(0x42), scan(0x42)]).merged_bytes();
+        assert_eq!(merged, Some(scan(0x42)));
+    }
+
+    #[test]
+    fn a_single_corrupted_scan_is_outvoted() {
+        let mut corrupted = scan(0x42);
+        corrupted[10] = 0x00;
+        corrupted[700] = 0xff;
+        let merged = accumulator(&[scan(0x42), corrupted, scan(0x42)]).merged_bytes();
+        assert_eq!(merged, Some(scan(0x42)));
+    }
+
+    #[test]
+    fn ties_go_to_the_scan_added_first() {
+        let mut second = scan(0x42);
+        second[10] = 0x00;
+        let merged = accumulator(&[scan(0x42), second.clone()]).merged_bytes().expect("both scans are full length");
+        assert_eq!(merged[10], 0x42);
+
+        let merged = accumulator(&[second, scan(0x42)]).merged_bytes().expect("both scans are full length");
+        assert_eq!(merged[10], 0x00);
+    }
+
+    #[test]
+    fn scans_of_other_lengths_are_left_out_of_the_vote() {
+        let merged = accumulator(&[scan(0x42), vec![0x00; LICENSE_LENGTH - 1], vec![0x00; LICENSE_LENGTH + 2]]).merged_bytes();
+        assert_eq!(merged, Some(scan(0x42)));
+
+        let short = accumulator(&[vec![0x42; 100], vec![0x42; LICENSE_LENGTH - 1]]);
+        assert_eq!(short.len(), 2);
+        assert_eq!(short.merged_bytes(), None);
+        assert!(matches!(short.result(), Err(DecodeError::InsufficientBytes)));
+    }
+
+    #[test]
+    fn fields_take_the_most_common_value_and_report_agreement() {
+        let scans = [json!({ "surname": "SMITH", "initials": "J" }), json!({ "surname": "SMITH", "initials": "J" }), json!({ "surname": "SM1TH", "initials": "J" })];
+        let (fields, confidence) = merge_fields(None, &scans, scans.len());
+        assert_eq!(Value::Object(fields), json!({ "surname": "SMITH", "initials": "J" }));
+        assert!((confidence - (2.0 / 3.0 + 1.0) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_merged_payload_wins_field_ties() {
+        let merged = json!({ "surname": "SMITH" });
+        let scans = [json!({ "surname": "SM1TH" })];
+        let (fields, confidence) = merge_fields(Some(&merged), &scans, 2);
+        assert_eq!(fields.get("surname"), Some(&json!("SMITH")));
+        assert_eq!(confidence, 0.0);
+    }
+
+    #[cfg(feature = "test-keys")]
+    #[test]
+    fn a_corrupted_scan_is_outvoted_end_to_end() {
+        let keys = drivers_license::test_keys();
+        let data = DriversLicenseData {
+            surname: "SMITH".to_string(),
+            id_number: "8503145800089".to_string(),
+            license_issue_number: "01".to_string(),
+            ..Default::default()
+        };
+        let payload = drivers_license::encode(&data, &keys).expect("the licence encodes");
+        let mut corrupted = payload.clone();
+        corrupted[20] ^= 0xff;
+
+        let mut accumulator = ScanAccumulator::with_options(ParseOptions { keys: vec![keys.version_keys()], ..Default::default() });
+        for scan in [&payload, &corrupted, &payload] {
+            accumulator.add(scan);
+        }
+        let result = accumulator.result().expect("the scans merge");
+        assert_eq!(result.scans, 3);
+        assert_eq!((result.data.surname.as_str(), result.data.id_number.as_str()), ("SMITH", "8503145800089"));
+        assert!(result.confidence > 0.5 && result.confidence < 1.0, "{}", result.confidence);
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::Read;
+use std::process::ExitCode;
+use wasm_license_decoder::error::DecodeError;
+use wasm_license_decoder::license::{self, ParseOptions};
+use wasm_license_decoder::{drivers_license, input, output, smart_id, vehicle_license};
+
+const USAGE: &str = "Usage: license-decode [--type drivers|vehicle|smart-id|auto] [--format raw|hex|base64] [--redact] [--fingerprint] [--canonical] [FILE]
+
+Decodes a driver's licence, vehicle licence disc or smart ID card payload and prints it as JSON.
+Reads FILE, or standard input when FILE is omitted or '-'.
+
+Options:
+  --type      The payload type. Defaults to auto, which detects it.
+  --format    How the payload is encoded. Defaults to raw bytes.
+  --redact    Mask the ID and licence numbers and drop the birthdate and photo.
+  --fingerprint
+              Add payload_sha256, a SHA-256 of the payload, to the output.
+  --canonical Print canonical JSON (sorted keys, no null or empty values) for hashing or signing.
+  --help      Show this message.";
+
+#[derive(Clone, Copy)]
+enum LicenseType {
+    Auto,
+    Drivers,
+    Vehicle,
+    SmartId,
+}
+
+#[derive(Clone, Copy)]
+enum InputFormat {
+    Raw,
+    Hex,
+    Base64,
+}
+
+struct Args {
+    license_type: LicenseType,
+    format: InputFormat,
+    redact: bool,
+    fingerprint: bool,
+    canonical: bool,
+    path: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let payload = match read_payload(args.path.as_deref()) {
+        Ok(payload) => payload,
+        Err(e) => {
+            eprintln!("error: failed to read {}: {}", args.path.as_deref().unwrap_or("standard input"), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match decode(&payload, &args) {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {} ({})", e, e.code());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Returns None when --help was requested.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args { license_type: LicenseType::Auto, format: InputFormat::Raw, redact: false, fingerprint: false, canonical: false, path: None };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--redact" => parsed.redact = true,
+            "--fingerprint" => parsed.fingerprint = true,
+            "--canonical" => parsed.canonical = true,
+            "--type" => {
+                parsed.license_type = match args.next().as_deref() {
+                    Some("auto") => LicenseType::Auto,
+                    Some("drivers") => LicenseType::Drivers,
+                    Some("vehicle") => LicenseType::Vehicle,
+                    Some("smart-id") => LicenseType::SmartId,
+                    other => return Err(format!("--type expects drivers, vehicle, smart-id or auto, not {}", other.unwrap_or("nothing"))),
+                }
+            }
+            "--format" => {
+                parsed.format = match args.next().as_deref() {
+                    Some("raw") => InputFormat::Raw,
+                    Some("hex") => InputFormat::Hex,
+                    Some("base64") => InputFormat::Base64,
+                    other => return Err(format!("--format expects raw, hex or base64, not {}", other.unwrap_or("nothing"))),
+                }
+            }
+            "-" => parsed.path = None,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            path if parsed.path.is_none() => parsed.path = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument {}", extra)),
+        }
+    }
+
+    Ok(Some(parsed))
+}
+
+fn read_payload(path: Option<&str>) -> std::io::Result<Vec<u8>> {
+    match path {
+        Some(path) => std::fs::read(path),
+        None => {
+            let mut payload = Vec::new();
+            std::io::stdin().read_to_end(&mut payload)?;
+            Ok(payload)
+        }
+    }
+}
+
+fn decode(payload: &[u8], args: &Args) -> Result<String, DecodeError> {
+    let bytes = match args.format {
+        InputFormat::Raw => payload.to_vec(),
+        InputFormat::Hex => input::decode_hex(std::str::from_utf8(payload)?)?,
+        InputFormat::Base64 => input::decode_base64(std::str::from_utf8(payload)?)?,
+    };
+
+    let mut options = ParseOptions::default();
+    options.drivers.redact = args.redact;
+    options.smart_id.redact = args.redact;
+    options.drivers.fingerprint = args.fingerprint;
+    options.vehicle.fingerprint = args.fingerprint;
+    options.smart_id.fingerprint = args.fingerprint;
+
+    match args.license_type {
+        LicenseType::Auto => to_json(&license::parse_bytes_with_options(&bytes, options)?, args),
+        LicenseType::Drivers => to_json(&drivers_license::parse_bytes_with_options(&bytes, options.drivers)?, args),
+        // Text dumps often end in a newline that isn't part of the disc.
+        LicenseType::Vehicle => {
+            to_json(&vehicle_license::parse_bytes_with_options(bytes.trim_ascii_end(), options.vehicle)?, args)
+        }
+        LicenseType::SmartId => to_json(&smart_id::parse_bytes_with_options(&bytes, options.smart_id)?, args),
+    }
+}
+
+fn to_json<T: serde::Serialize>(data: &T, args: &Args) -> Result<String, DecodeError> {
+    if args.canonical { output::to_canonical_json(data) } else { output::to_json_string(data, false) }
+}
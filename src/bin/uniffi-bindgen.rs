@@ -0,0 +1,5 @@
+// Generates the Kotlin and Swift bindings from the compiled library, e.g.
+// cargo run --features uniffi --bin uniffi-bindgen -- generate --library target/debug/libwasm_license_decoder.so --language kotlin --out-dir out
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
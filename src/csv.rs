@@ -0,0 +1,77 @@
+use serde_json::Value;
+use crate::fields::{self, FieldType};
+use crate::license::{License, LicenseFormat};
+
+const FORMATS: [LicenseFormat; 3] = [LicenseFormat::Drivers, LicenseFormat::Vehicle, LicenseFormat::SmartId];
+
+/// The CSV columns for `formats`: "type", then each format's fields in `describe_fields` order, with fields that
+/// several formats share (e.g. `surname`) listed once. Byte fields such as the photo are left out.
+pub fn columns(formats: &[LicenseFormat]) -> Vec<&'static str> {
+    let mut columns = vec!["type"];
+    for format in FORMATS.iter().filter(|format| formats.contains(format)) {
+        for field in fields::describe_fields(*format) {
+            if field.field_type != FieldType::Bytes && !columns.contains(&field.name) {
+                columns.push(field.name);
+            }
+        }
+    }
+    columns
+}
+
+/// One CSV row for `license`, in the order of `columns(&[license.format()])`, without a line ending.
+pub fn to_csv_row(license: &License) -> String {
+    row(license, &columns(&[license.format()]))
+}
+
+/// A header line and a row per licence, each ending in CRLF. The columns cover every format in the batch, so a
+/// session of one format always has the same columns.
+pub fn to_csv(licenses: &[License]) -> String {
+    let formats: Vec<LicenseFormat> = licenses.iter().map(License::format).collect();
+    let columns = columns(&formats);
+    let mut csv = columns.iter().map(|column| escape(column)).collect::<Vec<_>>().join(",");
+    csv.push_str("\r\n");
+    for license in licenses {
+        csv.push_str(&row(license, &columns));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn row(license: &License, columns: &[&str]) -> String {
+    let value = serde_json::to_value(license).unwrap_or(Value::Null);
+    columns
+        .iter()
+        .map(|&column| {
+            let cell = if column == "type" { &value["type"] } else { &value["data"][column] };
+            escape(&cell_text(cell))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Lists are joined with "; " and objects written as "key=value" pairs, e.g. "code=B issued=2010/05/21".
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(cell_text).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("; "),
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| (key, cell_text(value)))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(key, text)| format!("{}={}", key, text))
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => other.to_string(),
+    }
+}
+
+fn escape(cell: &str) -> String {
+    // Spreadsheets evaluate cells starting with these as formulas, so scanned text could run one.
+    let cell = if cell.starts_with(['=', '+', '-', '@']) { format!("'{}", cell) } else { cell.to_string() };
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
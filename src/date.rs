@@ -0,0 +1,170 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::warning::Warning;
+
+const MIN_YEAR: u16 = 1900;
+const MAX_YEAR: u16 = 2100;
+const MONTH_ABBREVIATIONS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+
+// Field order makes the derived ordering chronological.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct LicenseDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl LicenseDate {
+    pub fn new(year: u16, month: u8, day: u8) -> Self {
+        LicenseDate { year, month, day }
+    }
+
+    /// Parses "YYYY/MM/DD" or "YYYY-MM-DD", the day-first "DD/MM/YYYY" seen on some discs, and the
+    /// "DD MMM YYYY" of smart ID cards.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let [day, month, year] = value.split_whitespace().collect::<Vec<_>>()[..] {
+            let month = MONTH_ABBREVIATIONS.iter().position(|known| known.eq_ignore_ascii_case(month))? as u8 + 1;
+            return Some(LicenseDate::new(year.parse().ok()?, month, day.parse().ok()?));
+        }
+        let parts: Vec<&str> = value.trim().split(['/', '-']).collect();
+        let [first, month, last] = parts[..] else {
+            return None;
+        };
+        let (year, day) = if last.len() == 4 { (last, first) } else { (first, last) };
+        Some(LicenseDate::new(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?))
+    }
+
+    /// True when the date exists in the calendar and falls in a sensible range for a license.
+    pub fn is_plausible(&self) -> bool {
+        (MIN_YEAR..=MAX_YEAR).contains(&self.year)
+            && (1..=12).contains(&self.month)
+            && self.day >= 1
+            && self.day <= days_in_month(self.year, self.month)
+    }
+
+    /// Days since 1970-01-01, negative for earlier dates.
+    pub fn days_since_epoch(&self) -> i64 {
+        // Counts from March so the leap day falls at the end of the year.
+        let month = self.month as i64;
+        let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Days from this date to `other`, negative when `other` is earlier.
+    pub fn days_until(&self, other: LicenseDate) -> i64 {
+        other.days_since_epoch() - self.days_since_epoch()
+    }
+
+    /// Whole years from this date to `other`, e.g. someone's age on `other` when this is their birthdate.
+    pub fn years_until(&self, other: LicenseDate) -> i32 {
+        let years = other.year as i32 - self.year as i32;
+        if (other.month, other.day) < (self.month, self.day) { years - 1 } else { years }
+    }
+
+    pub fn to_iso_string(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for LicenseDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}/{:02}/{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A date field as it appears in the output: the text read from the license, or a structured date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(untagged)]
+pub enum DateValue {
+    Text(String),
+    Date(LicenseDate),
+}
+
+impl DateValue {
+    pub fn as_date(&self) -> Option<LicenseDate> {
+        match self {
+            DateValue::Text(text) => LicenseDate::parse(text),
+            DateValue::Date(date) => Some(*date),
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            DateValue::Text(text) => Some(text),
+            DateValue::Date(_) => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, DateValue::Text(text) if text.is_empty())
+    }
+}
+
+impl Default for DateValue {
+    fn default() -> Self {
+        DateValue::Text(String::new())
+    }
+}
+
+impl From<String> for DateValue {
+    fn from(text: String) -> Self {
+        DateValue::Text(text)
+    }
+}
+
+impl fmt::Display for DateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateValue::Text(text) => write!(f, "{}", text),
+            DateValue::Date(date) => write!(f, "{}", date),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    /// Dates as read from the license, e.g. "2024/03/31".
+    #[default]
+    Raw,
+    /// Validated "YYYY-MM-DD" strings.
+    Iso,
+    /// Validated `{ year, month, day }` objects.
+    Structured,
+}
+
+/// Rewrites a date field in the requested format. Implausible dates are cleared and reported in `warnings`.
+pub(crate) fn apply_format(field: &str, value: &mut DateValue, format: DateFormat, warnings: &mut Vec<Warning>) {
+    if format == DateFormat::Raw || value.is_empty() {
+        return;
+    }
+
+    match value.as_date().filter(LicenseDate::is_plausible) {
+        Some(date) if format == DateFormat::Iso => *value = DateValue::Text(date.to_iso_string()),
+        Some(date) => *value = DateValue::Date(date),
+        None => {
+            warnings.push(Warning::for_field("IMPLAUSIBLE_DATE", field, format!("Field {} has an implausible date {}", field, value)));
+            *value = DateValue::default();
+        }
+    }
+}
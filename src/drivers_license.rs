@@ -1,330 +1,1246 @@
+use std::borrow::Cow;
+use std::sync::LazyLock;
 use num_bigint_dig::BigUint;
-use std::error::Error;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use crate::country::{self, CountryValue};
+use crate::date::{self, DateFormat, DateValue, LicenseDate};
+use crate::error::{DecodeError, Section};
+use crate::gender::{Gender, GenderFormat, GenderValue};
+use crate::id_number::{self, IdDocumentType, IdNumberValidation};
+use crate::license_code;
+use crate::output;
+use crate::prdp::ProfessionalPermit;
+use crate::reader::{NibbleReader, Reader};
+use crate::restriction::DriverRestriction;
+use crate::text::TextEncoding;
+use crate::validation::{self, ParseConfidence};
+use crate::warning::Warning;
 
-#[derive(Debug)]
-enum Version {
-    V1,
-    V2,
+const EMPTY_DATE_NIBBLE: u8 = 0x0a;
+const STRING_SECTION_MARKER: u8 = 0x82;
+const ID_NUMBER_LENGTH: usize = 13;
+const NIBBLE_SECTION_TERMINATOR: u8 = 0x57;
+const LICENSE_LENGTH: usize = 720;
+const HEADER_SEARCH_WINDOW: usize = 16;
+// RSA blocks start with a 0x00 pad byte, which keeps their value below the modulus. It isn't part of the data.
+const BLOCK_PAD: u8 = 0x00;
+
+struct VersionSpec {
+    version: u8,
+    header: [u8; 4],
+    key_128: &'static str,
+    key_74: &'static str,
+}
+
+// Adding a version only requires a new entry here and its keys in `BUILT_IN_KEYS_HEX`.
+// Version 3 cards are not listed yet: their header and key set have not been published.
+const VERSIONS: [VersionSpec; 2] = [
+    VersionSpec { version: 1, header: [0x01, 0xe1, 0x02, 0x45], key_128: "pk_v1_128", key_74: "pk_v1_74" },
+    VersionSpec { version: 2, header: [0x01, 0x9b, 0x09, 0x45], key_128: "pk_v2_128", key_74: "pk_v2_74" },
+];
+
+/// The outcome of an age check, without the birthdate or ID number it was derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct AgeVerification {
+    pub older_than: bool,
+    pub license_valid: bool,
 }
 
-#[derive(Debug, Serialize)]
-#[allow(dead_code)]
+/// A licence code together with the issue date and restriction read from the same slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct LicenseCode {
+    pub code: String,
+    pub issued: Option<DateValue>,
+    pub restriction: Option<String>,
+}
+
+// Deserializes the same shape it serializes, so parsed licences can be stored and read back.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(default)]
 pub struct DriversLicenseData {
-    vehicle_codes: Vec<String>,
-    surname: String,
-    initials: String,
-    pr_dp_code: Option<String>,
-    id_country_of_issue: String,
-    license_country_of_issue: String,
-    vehicle_restrictions: Vec<String>,
-    license_number: String,
-    id_number: String,
-    id_number_type: String,
-    license_code_issue_dates: Vec<String>,
-    driver_restriction_codes: String,
-    prd_permit_expiry_date: Option<String>,
-    license_issue_number: String,
-    birthdate: String,
-    license_issue_date: String,
-    license_expiry_date: String,
-    gender: String,
-    image_width: u8,
-    image_height: u8,
-}
-
-#[derive(Debug)]
-pub enum DriversLicenseError {
-    InsufficientBytes,
-    UnknownVersion,
-}
-
-impl Error for DriversLicenseError {}
-
-impl std::fmt::Display for DriversLicenseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DriversLicenseError::InsufficientBytes => write!(f, "Invalid license (insufficient bytes)"),
-            DriversLicenseError::UnknownVersion => write!(f, "Unrecognized license version"),
+    pub licenses: Vec<LicenseCode>,
+    pub vehicle_codes: Vec<String>,
+    pub surname: String,
+    pub initials: String,
+    pub initials_list: Vec<String>,
+    pub pr_dp_code: Option<String>,
+    pub id_country_of_issue: CountryValue,
+    pub license_country_of_issue: CountryValue,
+    pub vehicle_restrictions: Vec<String>,
+    pub license_number: String,
+    pub id_number: String,
+    pub id_number_type: String,
+    #[cfg_attr(feature = "wasm", tsify(type = "{ code: string; description: string }"))]
+    #[cfg_attr(feature = "schema", schemars(with = "crate::schema::CodedValue"))]
+    pub id_document_type: IdDocumentType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_number_validation: Option<IdNumberValidation>,
+    pub license_code_issue_dates: Vec<DateValue>,
+    pub driver_restriction_codes: String,
+    #[cfg_attr(feature = "wasm", tsify(type = "{ code: string; description: string }[]"))]
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<crate::schema::CodedValue>"))]
+    pub driver_restrictions: Vec<DriverRestriction>,
+    pub prd_permit_expiry_date: Option<DateValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prdp: Option<ProfessionalPermit>,
+    pub license_issue_number: String,
+    #[serde(skip_serializing_if = "DateValue::is_empty")]
+    pub birthdate: DateValue,
+    pub license_issue_date: DateValue,
+    pub license_expiry_date: DateValue,
+    pub gender: GenderValue,
+    pub image_width: u8,
+    pub image_height: u8,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "output::serialize_optional_bytes", deserialize_with = "output::deserialize_optional_bytes")]
+    #[cfg_attr(feature = "wasm", tsify(type = "Uint8Array"))]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<Vec<u8>>"))]
+    pub image: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// How far the data can be trusted. Only set when parsing leniently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<ParseConfidence>,
+    /// SHA-256 of the payload as hex, when the `fingerprint` option is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+    /// Parts of the decrypted payload a lenient parse skipped to resynchronise after garbled data.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_ranges: Vec<SkippedRange>,
+}
+
+/// Bytes `start..end` of the decrypted payload, whose fields were read from garbled data or not at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct SkippedRange {
+    pub start: u32,
+    pub end: u32,
+    pub reason: String,
+}
+
+impl DriversLicenseData {
+    /// Canonical key for deduplicating scans of the same card, usable in a `HashMap` or `HashSet`.
+    /// Derived only from stable identity fields (ID number, license number and issue number),
+    /// never from volatile ones like the photo, so repeated scans of one card yield the same key.
+    pub fn identity_key(&self) -> String {
+        format!("{}|{}|{}", self.id_number.trim(), self.license_number.trim(), self.license_issue_number.trim())
+    }
+
+    /// Initials and surname as printed on the card, e.g. "J SMITH".
+    pub fn full_name(&self) -> String {
+        [self.initials.as_str(), self.surname.as_str()].iter().filter(|s| !s.is_empty()).copied().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Whether the holder has licence code `code` itself, e.g. "EB". See `license_code::allows_driving` for implied codes.
+    pub fn has_code(&self, code: &str) -> bool {
+        self.vehicle_codes.iter().any(|held| held.trim().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// The licence expiry date, when it parses to a real calendar date.
+    pub fn expiry(&self) -> Option<LicenseDate> {
+        self.license_expiry_date.as_date().filter(LicenseDate::is_plausible)
+    }
+
+    /// A licence remains valid through its expiry date. None when the expiry date can't be read.
+    pub fn is_expired(&self, today: LicenseDate) -> Option<bool> {
+        self.expiry().map(|expiry| expiry < today)
+    }
+
+    /// Negative once the licence has expired. None when the expiry date can't be read.
+    pub fn days_until_expiry(&self, today: LicenseDate) -> Option<i64> {
+        self.expiry().map(|expiry| today.days_until(expiry))
+    }
+
+    /// Whether the licence had been issued and had not yet expired on `date`.
+    /// None when the dates can't be read.
+    pub fn is_valid_on(&self, date: LicenseDate) -> Option<bool> {
+        let issued = self.license_issue_date.as_date().filter(LicenseDate::is_plausible)?;
+        Some(issued <= date && !self.is_expired(date)?)
+    }
+
+    /// Masks personal identifiers for storage where full PII isn't permitted (e.g. under POPIA):
+    /// the ID number keeps its first four digits, the licence number its last three, and the
+    /// birthdate and photo are dropped, along with warnings that give away the birthdate or the holder's age.
+    pub fn redact(&mut self) {
+        self.id_number = mask(&self.id_number, 4, 0);
+        self.license_number = mask(&self.license_number, 0, 3);
+        self.birthdate = DateValue::default();
+        self.image = None;
+        self.warnings.retain(|warning| !warning.reveals_birthdate());
+    }
+
+    fn format_dates(&mut self, format: DateFormat) {
+        let warnings = &mut self.warnings;
+        for issue_date in &mut self.license_code_issue_dates {
+            date::apply_format("license_code_issue_dates", issue_date, format, warnings);
+        }
+        for issued in self.licenses.iter_mut().filter_map(|license| license.issued.as_mut()) {
+            date::apply_format("licenses.issued", issued, format, warnings);
+        }
+        if let Some(expiry_date) = &mut self.prd_permit_expiry_date {
+            date::apply_format("prd_permit_expiry_date", expiry_date, format, warnings);
+        }
+        if let Some(expiry_date) = self.prdp.as_mut().and_then(|prdp| prdp.expiry_date.as_mut()) {
+            date::apply_format("prdp.expiry_date", expiry_date, format, warnings);
+        }
+        date::apply_format("birthdate", &mut self.birthdate, format, warnings);
+        date::apply_format("license_issue_date", &mut self.license_issue_date, format, warnings);
+        date::apply_format("license_expiry_date", &mut self.license_expiry_date, format, warnings);
+
+        self.license_code_issue_dates.retain(|issue_date| !issue_date.is_empty());
+        for license in &mut self.licenses {
+            license.issued = license.issued.take().filter(|issued| !issued.is_empty());
+        }
+        self.prd_permit_expiry_date = self.prd_permit_expiry_date.take().filter(|expiry_date| !expiry_date.is_empty());
+        if let Some(prdp) = &mut self.prdp {
+            prdp.expiry_date = prdp.expiry_date.take().filter(|expiry_date| !expiry_date.is_empty());
         }
     }
 }
 
-pub fn parse_bytes(bytes: Vec<u8>) -> Result<DriversLicenseData, Box<dyn Error>> {
-    if bytes.len() != 720 {
-        return Err(Box::new(DriversLicenseError::InsufficientBytes));
+// Replaces every character except the first `keep_start` and last `keep_end` with '*'.
+pub(crate) fn mask(value: &str, keep_start: usize, keep_end: usize) -> String {
+    let length = value.chars().count();
+    value
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i < keep_start || i + keep_end >= length { c } else { '*' })
+        .collect()
+}
+
+/// An RSA public key as hex strings, in the same form as the built-in keys.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct PublicKeyHex {
+    pub modulus: String,
+    pub exponent: String,
+}
+
+/// Keys for the license version identified by `header`, the first four bytes of the payload.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct VersionKeys {
+    pub header: [u8; 4],
+    #[serde(alias = "key128")]
+    pub key_128: PublicKeyHex,
+    #[serde(alias = "key74")]
+    pub key_74: PublicKeyHex,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(default, rename = "DriversParseOptions")]
+pub struct ParseOptions {
+    /// Accept buffers longer than 720 bytes, using the first 720 bytes after the header.
+    #[serde(alias = "tolerantLength")]
+    pub tolerant_length: bool,
+    /// Include the raw bytes of the cardholder photo section in the output.
+    #[serde(alias = "includeImage")]
+    pub include_image: bool,
+    /// Return the fields decoded so far, plus a warning, instead of failing on corrupt later sections.
+    pub lenient: bool,
+    /// Keys that take precedence over the built-in ones, e.g. for test cards or rotated keys.
+    /// Headers that aren't built in are accepted as long as keys are supplied for them.
+    pub keys: Vec<VersionKeys>,
+    /// How date fields are emitted.
+    #[serde(alias = "dateFormat")]
+    pub date_format: DateFormat,
+    /// How the holder's gender is emitted.
+    #[serde(alias = "genderFormat")]
+    pub gender_format: GenderFormat,
+    /// Emit country codes as `{ code, name }` with the ISO 3166 name.
+    #[serde(alias = "expandCountries")]
+    pub expand_countries: bool,
+    /// Mask personal identifiers in the output, see `DriversLicenseData::redact`.
+    pub redact: bool,
+    /// How the name and other text fields are decoded. Latin-1 unless the issuer is known to use another code page.
+    #[serde(alias = "textEncoding")]
+    pub text_encoding: TextEncoding,
+    /// Add `payload_sha256`, a hash of the payload for deduplication and audit trails. It is kept when redacting.
+    pub fingerprint: bool,
+    /// Emit camelCase keys (`licenseNumber`) instead of snake_case. Only affects the JS bindings.
+    #[serde(alias = "camelCase")]
+    pub camel_case: bool,
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<DriversLicenseData, DecodeError> {
+    parse_bytes_with_options(bytes, ParseOptions::default())
+}
+
+pub fn parse_bytes_with_options(bytes: &[u8], options: ParseOptions) -> Result<DriversLicenseData, DecodeError> {
+    let (blocks, mut warnings) = decrypt_with_options(bytes, &options)?;
+
+    let (payload, block_ends) = join_block_data(&blocks);
+    let mut data = parse_data(&payload, &block_ends, options.lenient, options.text_encoding)?;
+    warnings.append(&mut data.warnings);
+    data.warnings = warnings;
+    if !options.include_image {
+        data.image = None;
+    }
+    data.format_dates(options.date_format);
+    data.gender = GenderValue::new(data.gender.gender(), options.gender_format);
+    if options.expand_countries {
+        data.id_country_of_issue.expand();
+        data.license_country_of_issue.expand();
+    }
+    if options.redact {
+        data.redact();
+    }
+    if options.fingerprint {
+        data.payload_sha256 = Some(output::payload_sha256(bytes));
     }
+    Ok(data)
+}
 
-    let version = match bytes.get(..4) {
-        Some([0x01, 0xe1, 0x02, 0x45]) => Version::V1,
-        Some([0x01, 0x9b, 0x09, 0x45]) => Version::V2,
-        _ => return Err(Box::new(DriversLicenseError::UnknownVersion)),
-    };
+/// Runs version detection and RSA decryption only, returning the full decrypted payload: each block's value in
+/// turn, without its leading zero bytes.
+pub fn decrypt_bytes(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let (blocks, _) = decrypt_with_options(bytes, &ParseOptions::default())?;
+    Ok(join_block_values(&blocks))
+}
+
+// Returns the decrypted blocks at their full 128 or 74 byte length.
+fn decrypt_with_options(bytes: &[u8], options: &ParseOptions) -> Result<(Vec<Vec<u8>>, Vec<Warning>), DecodeError> {
+    let mut warnings = Vec::new();
 
-    let decrypted: Result<Vec<u8>, Box<dyn Error>> = match version {
-        Version::V1 => decrypt_v1(&bytes[6..]),
-        Version::V2 => decrypt_v2(&bytes[6..]),
+    let bytes = if options.tolerant_length && bytes.len() > LICENSE_LENGTH {
+        let offset = locate_header(bytes, options).ok_or(DecodeError::UnknownVersion)?;
+        if offset > 0 {
+            warnings.push(Warning::new("IGNORED_BYTES", format!("Ignored {} leading bytes before the license header", offset)));
+        }
+        let trailing = bytes.len().saturating_sub(offset + LICENSE_LENGTH);
+        if trailing > 0 {
+            warnings.push(Warning::new("IGNORED_BYTES", format!("Ignored {} trailing bytes", trailing)));
+        }
+        bytes.get(offset..offset + LICENSE_LENGTH).ok_or(DecodeError::InsufficientBytes)?
+    } else {
+        bytes
     };
 
-    parse_data(decrypted)
+    if bytes.len() != LICENSE_LENGTH {
+        return Err(DecodeError::InsufficientBytes);
+    }
+
+    let (pk_128, pk_74) = load_keys(bytes, options)?;
+
+    let decrypted = decrypt_payload(bytes.get(6..).ok_or(DecodeError::InsufficientBytes)?, &pk_128, &pk_74)?;
+
+    Ok((decrypted, warnings))
+}
+
+/// Checks the holder is at least `years` old on `today`, and that the licence hasn't expired.
+/// Both checks fail closed when the relevant date can't be read.
+pub fn verify_age(bytes: &[u8], years: u16, today: LicenseDate) -> Result<AgeVerification, DecodeError> {
+    let data = parse_bytes(bytes)?;
+    let birthdate = data.birthdate.as_date().filter(LicenseDate::is_plausible);
+    Ok(AgeVerification {
+        older_than: birthdate.is_some_and(|birthdate| birthdate.years_until(today) >= years as i32),
+        license_valid: data.is_expired(today) == Some(false),
+    })
+}
+
+pub fn extract_image(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let options = ParseOptions { include_image: true, ..ParseOptions::default() };
+    let data = parse_bytes_with_options(bytes, options)?;
+    data.image.ok_or(DecodeError::MissingImage)
 }
 
-fn decrypt_v1(payload: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
-    let pk_128 = load_public_key("pk_v1_128")?;
-    let pk_74 = load_public_key("pk_v1_74")?;
-    decrypt_payload(payload, &pk_128, &pk_74)
+pub(crate) fn has_known_header(bytes: &[u8]) -> bool {
+    detect_version(bytes).is_some()
 }
 
-fn decrypt_v2(payload: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
-    let pk_128 = load_public_key("pk_v2_128")?;
-    let pk_74 = load_public_key("pk_v2_74")?;
-    decrypt_payload(payload, &pk_128, &pk_74)
+/// The licence version from the payload header, without decrypting. None for unknown headers.
+pub fn license_version(bytes: &[u8]) -> Option<u8> {
+    detect_version(bytes).map(|spec| spec.version)
 }
 
+/// Whether the `tolerant_length` option lets a payload longer than 720 bytes be read, as it has a header near its start.
+pub(crate) fn has_tolerated_header(bytes: &[u8], options: &ParseOptions) -> bool {
+    options.tolerant_length && bytes.len() > LICENSE_LENGTH && locate_header(bytes, options).is_some()
+}
+
+fn detect_version(bytes: &[u8]) -> Option<&'static VersionSpec> {
+    let header = bytes.get(..4)?;
+    VERSIONS.iter().find(|spec| spec.header == header)
+}
+
+fn custom_keys<'a>(bytes: &[u8], options: &'a ParseOptions) -> Option<&'a VersionKeys> {
+    options.keys.iter().find(|keys| bytes.starts_with(&keys.header))
+}
+
+// Finds the header within a small window, as long as a full license still fits after it.
+fn locate_header(bytes: &[u8], options: &ParseOptions) -> Option<usize> {
+    let last_offset = bytes.len().saturating_sub(LICENSE_LENGTH).min(HEADER_SEARCH_WINDOW);
+    (0..=last_offset).find(|&offset| {
+        let candidate = bytes.get(offset..).unwrap_or_default();
+        custom_keys(candidate, options).is_some() || detect_version(candidate).is_some()
+    })
+}
+
+type KeyPair = (Cow<'static, PublicKey>, Cow<'static, PublicKey>);
+
+fn load_keys(bytes: &[u8], options: &ParseOptions) -> Result<KeyPair, DecodeError> {
+    if let Some(keys) = custom_keys(bytes, options) {
+        let pk_128 = PublicKey::from_hex("custom_128", &keys.key_128.modulus, &keys.key_128.exponent)?;
+        let pk_74 = PublicKey::from_hex("custom_74", &keys.key_74.modulus, &keys.key_74.exponent)?;
+        return Ok((Cow::Owned(pk_128), Cow::Owned(pk_74)));
+    }
+
+    let spec = detect_version(bytes).ok_or(DecodeError::UnknownVersion)?;
+    Ok((Cow::Borrowed(load_public_key(spec.key_128)?), Cow::Borrowed(load_public_key(spec.key_74)?)))
+}
+
+#[derive(Clone)]
 struct PublicKey {
     n: BigUint, // Modulus
     e: BigUint, // Exponent
 }
 
-fn load_public_key(key_name: &str) -> Result<PublicKey, Box<dyn Error>> {
-    let (modulus_hex, exponent_hex) = match key_name {
-        "pk_v1_128" => (
-            "00fed2e1c27e3363316e77317a7a52c54981395186be4974760c72518d63e0544a48d088b332c5b0c370c765d65d983c1f9de0a42b310ccc07ae770bd2b61d6a4dcceac757689bdcbf608478faf312f6087cc496c3762cf5c4651caecda3499fae7edb7e0e3e18eb304170e91ed5b156aace6f432d6eca6cc35851de8c678f67",
-            "00bb797ffdec7f9e42c9d6f79b137059db",
-        ),
-        "pk_v1_74" => (
-            "00ff3cec6b5f40e3c3661451b9fcfaef3aeb06dc2329c0e6f4dccc9279726716ce15bbe05eed2c5711bcf8f5b6c8f7276db5c43bfaa3040dc01ab14b9c4d16f71c0ce5ea953f0c754c6b17",
-            "00db05ba822d9acc33fab7d8f427f9ce65",
-        ),
-        "pk_v2_128" => (
-            "00ca9f18ef6c3f3fa4c5a461fea54ab19406ba5ecd746d60a27492dca3d74e3b5c1d315f7b10383241809b029ebbd5de4d116030cc57f7d5a6c9a16f373bb14a508523f7e80a4c744d9085663a4a1472d7af2c56ae41b5065f7efa0293bd3278ad693546f9f16219b79ff471a3636824cffcdb63a8ed8059e6b9a4f0db895381cb",
-            "187092da6454ceb1853e6915f8466a05",
-        ),
-        "pk_v2_74" => (
-            "00b404a0df11d1cacf1a1a048d4d573f953a62c583d74925927561a6d7a1e2b14042526af70b550547390ea6ec748d30fdb81adb490e0c36a1986b404b2f5f69ef5da1b663e59509130e7",
-            "309cfed9719fe2a5e20c9bb44765382b",
-        ),
-        _ => return Err(format!("Unknown key name {}", key_name).into()),
-    };
+impl PublicKey {
+    fn from_hex(key_name: &str, modulus_hex: &str, exponent_hex: &str) -> Result<Self, DecodeError> {
+        let n = BigUint::parse_bytes(modulus_hex.replace(":", "").as_bytes(), 16)
+            .ok_or_else(|| DecodeError::InvalidKey(key_name.to_string()))?;
+        let e = BigUint::parse_bytes(exponent_hex.replace(":", "").as_bytes(), 16)
+            .ok_or_else(|| DecodeError::InvalidKey(key_name.to_string()))?;
 
-    let n = BigUint::parse_bytes(modulus_hex.replace(":", "").as_bytes(), 16)
-        .ok_or_else(|| format!("Failed to parse modulus for {}", key_name))?;
-    let e = BigUint::parse_bytes(exponent_hex.replace(":", "").as_bytes(), 16)
-        .ok_or_else(|| format!("Failed to parse exponent for {}", key_name))?;
+        // A zero modulus would make modpow panic.
+        if n == BigUint::default() {
+            return Err(DecodeError::InvalidKey(key_name.to_string()));
+        }
 
-    Ok(PublicKey { n, e })
+        Ok(PublicKey { n, e })
+    }
+}
+
+const BUILT_IN_KEYS_HEX: [(&str, &str, &str); 4] = [
+    (
+        "pk_v1_128",
+        "00fed2e1c27e3363316e77317a7a52c54981395186be4974760c72518d63e0544a48d088b332c5b0c370c765d65d983c1f9de0a42b310ccc07ae770bd2b61d6a4dcceac757689bdcbf608478faf312f6087cc496c3762cf5c4651caecda3499fae7edb7e0e3e18eb304170e91ed5b156aace6f432d6eca6cc35851de8c678f67",
+        "00bb797ffdec7f9e42c9d6f79b137059db",
+    ),
+    (
+        "pk_v1_74",
+        "00ff3cec6b5f40e3c3661451b9fcfaef3aeb06dc2329c0e6f4dccc9279726716ce15bbe05eed2c5711bcf8f5b6c8f7276db5c43bfaa3040dc01ab14b9c4d16f71c0ce5ea953f0c754c6b17",
+        "00db05ba822d9acc33fab7d8f427f9ce65",
+    ),
+    (
+        "pk_v2_128",
+        "00ca9f18ef6c3f3fa4c5a461fea54ab19406ba5ecd746d60a27492dca3d74e3b5c1d315f7b10383241809b029ebbd5de4d116030cc57f7d5a6c9a16f373bb14a508523f7e80a4c744d9085663a4a1472d7af2c56ae41b5065f7efa0293bd3278ad693546f9f16219b79ff471a3636824cffcdb63a8ed8059e6b9a4f0db895381cb",
+        "187092da6454ceb1853e6915f8466a05",
+    ),
+    (
+        "pk_v2_74",
+        "00b404a0df11d1cacf1a1a048d4d573f953a62c583d74925927561a6d7a1e2b14042526af70b550547390ea6ec748d30fdb81adb490e0c36a1986b404b2f5f69ef5da1b663e59509130e7",
+        "309cfed9719fe2a5e20c9bb44765382b",
+    ),
+];
+
+// Parsed once on first use, so repeated scans don't redo the bignum parsing.
+static BUILT_IN_KEYS: LazyLock<Vec<(&str, PublicKey)>> = LazyLock::new(|| {
+    BUILT_IN_KEYS_HEX
+        .iter()
+        .map(|&(name, modulus_hex, exponent_hex)| {
+            (name, PublicKey::from_hex(name, modulus_hex, exponent_hex).expect("built-in keys are valid hex"))
+        })
+        .collect()
+});
+
+fn load_public_key(key_name: &str) -> Result<&'static PublicKey, DecodeError> {
+    BUILT_IN_KEYS
+        .iter()
+        .find(|(name, _)| *name == key_name)
+        .map(|(_, key)| key)
+        .ok_or_else(|| DecodeError::UnknownKey(key_name.to_string()))
 }
 
-fn decrypt_payload(payload: &[u8], pk_128: &PublicKey, pk_74: &PublicKey) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut decrypted: Vec<u8> = Vec::new();
+fn decrypt_payload(payload: &[u8], pk_128: &PublicKey, pk_74: &PublicKey) -> Result<Vec<Vec<u8>>, DecodeError> {
+    if payload.len() <= 5 * 128 {
+        return Err(DecodeError::DecryptFailed);
+    }
+
+    let mut blocks: Vec<Vec<u8>> = Vec::new();
 
     for chunk in payload.chunks(128).take(5) {
-        let block: Vec<u8> = decrypt_block(chunk, pk_128)?;
-        decrypted.extend_from_slice(&block);
+        blocks.push(decrypt_block(chunk, pk_128)?);
     }
 
-    let final_block: Vec<u8> = decrypt_block(&payload[5 * 128..], pk_74)?;
-    decrypted.extend_from_slice(&final_block);
+    blocks.push(decrypt_block(payload.get(5 * 128..).ok_or(DecodeError::DecryptFailed)?, pk_74)?);
 
-    Ok(decrypted)
+    Ok(blocks)
 }
 
-fn decrypt_block(block: &[u8], key: &PublicKey) -> Result<Vec<u8>, Box<dyn Error>> {
+// Left-pads the output to the block's length, as `to_bytes_be` drops leading zero bytes.
+fn decrypt_block(block: &[u8], key: &PublicKey) -> Result<Vec<u8>, DecodeError> {
     let input: BigUint = BigUint::from_bytes_be(block);
     let output: BigUint = input.modpow(&key.e, &key.n);
-    let decrypted_bytes: Vec<u8> = output.to_bytes_be();
+    let output_bytes: Vec<u8> = output.to_bytes_be();
+    let mut decrypted_bytes = vec![0; block.len().saturating_sub(output_bytes.len())];
+    decrypted_bytes.extend(output_bytes);
     Ok(decrypted_bytes)
 }
 
-fn parse_data(data: Result<Vec<u8>, Box<dyn Error>>) -> Result<DriversLicenseData, Box<dyn Error>> {
-    let data = data?;
-    let mut index = 0;
+// Each block's value as `to_bytes_be` returns it, which is what `decrypt_bytes` has always returned.
+fn join_block_values(blocks: &[Vec<u8>]) -> Vec<u8> {
+    blocks.iter().flat_map(|block| block.iter().skip_while(|&&b| b == 0)).copied().collect()
+}
 
-    for (i, &byte) in data.iter().enumerate() {
-        if byte == 0x82 {
-            index = i;
-            break;
+// The data the fields are read from, and the offset where each block's part of it ends: each block less its pad
+// byte. A block without one is kept whole, so this only differs from `join_block_values` when a block's data itself
+// starts with a zero byte.
+fn join_block_data(blocks: &[Vec<u8>]) -> (Vec<u8>, Vec<usize>) {
+    let mut data = Vec::new();
+    let mut block_ends = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        data.extend_from_slice(block.strip_prefix(&[BLOCK_PAD]).unwrap_or(block));
+        block_ends.push(data.len());
+    }
+    (data, block_ends)
+}
+
+fn parse_data(data: &[u8], block_ends: &[usize], lenient: bool, encoding: TextEncoding) -> Result<DriversLicenseData, DecodeError> {
+    let mut license = DriversLicenseData::default();
+
+    let result = parse_fields(data, &mut license, encoding, lenient).map_err(|err| err.in_blocks(block_ends));
+    if lenient {
+        let complete = result.is_ok() && license.skipped_ranges.is_empty();
+        license.confidence = Some(validation::parse_confidence(&license, complete));
+    }
+    match result {
+        Ok(()) => Ok(license),
+        Err(err) if lenient => {
+            let warning = Warning::new("PARTIAL_DATA", format!("Returned partial data: {}", err));
+            license.warnings.push(Warning { field: err.field().map(str::to_string), ..warning });
+            Ok(license)
         }
+        Err(err) => Err(err),
     }
+}
 
-    index += 2;
+// Fills in fields as they are read, so a failure part way through leaves everything before it populated.
+// The payload is a string section, a nibble-packed binary section ending in 0x57, then the image section.
+fn parse_fields(data: &[u8], license: &mut DriversLicenseData, encoding: TextEncoding, lenient: bool) -> Result<(), DecodeError> {
+    let mut reader = Reader::new(data);
 
-    let (vehicle_codes, new_index) = read_strings(&data, index, 3)?;
-    index = new_index;
+    let section_start = data.iter().position(|&b| b == STRING_SECTION_MARKER).unwrap_or(0);
+    reader.seek(section_start + 2);
+    reader.set_section(Section::Strings);
 
-    let (surname, new_index, _) = read_string(&data, index)?;
-    index = new_index;
+    let strings_start = reader.position();
+    let string_section = parse_string_section(&mut reader, license, encoding);
+    // A string section that ran out of data, or ended on an unknown ID document type, has lost its alignment.
+    let misaligned = string_section.is_err() || matches!(license.id_document_type, IdDocumentType::Unknown(_));
+    let (vehicle_codes, vehicle_restrictions) = match string_section {
+        _ if lenient && misaligned => match find_id_number(data, strings_start) {
+            Some(position) => {
+                license.skipped_ranges.push(SkippedRange {
+                    start: strings_start as u32,
+                    end: position as u32,
+                    reason: "String section misaligned; resumed at the ID number".to_string(),
+                });
+                license.warnings.push(Warning::new(
+                    "RESYNCHRONISED",
+                    format!("Resynchronised on the ID number at byte {}; fields read before it may be garbled", position),
+                ));
+                reader.seek(position);
+                read_id_number(&mut reader, license, encoding)?;
+                (Vec::new(), Vec::new())
+            }
+            None => string_section?,
+        },
+        string_section => string_section?,
+    };
+
+    let mut nibbles = reader.read_nibbles(NIBBLE_SECTION_TERMINATOR);
+    let issue_dates = read_nibble_date_slots(&mut nibbles, 4, "licence code issue dates")?;
+    license.license_code_issue_dates = issue_dates.iter().flatten().map(|date| date.to_string().into()).collect();
+    license.licenses = pair_license_codes(&vehicle_codes, &vehicle_restrictions, &issue_dates);
+    parse_binary_section(&mut nibbles, license)?;
+
+    reader.set_section(Section::ImageHeader);
+    parse_image_section(&mut reader, license)
+}
 
-    let (initials, new_index, delimiter) = read_string(&data, index)?;
-    index = new_index;
+// Returns the vehicle code and restriction slots, which line up with the issue dates in the binary section.
+fn parse_string_section(reader: &mut Reader, license: &mut DriversLicenseData, encoding: TextEncoding) -> Result<(Vec<String>, Vec<String>), DecodeError> {
+    let vehicle_codes = read_strings(reader, 3, encoding);
+    license.vehicle_codes = non_empty(&vehicle_codes);
+
+    license.surname = read_string(reader, "surname", encoding)?.0.trim().to_string();
+
+    // 0xe0 after the initials means a PrDP code follows, 0xe1 that there is none.
+    let (initials, delimiter) = read_string(reader, "initials", encoding)?;
+    license.initials = initials.trim().to_string();
+    license.initials_list = split_initials(&license.initials);
 
-    let mut pr_dp_code = None;
     if delimiter == 0xe0 {
-        let (code, new_index, _) = read_string(&data, index)?;
-        index = new_index;
-        pr_dp_code = Some(code);
+        // Some cards write the 0xe0 with an empty PrDP field.
+        license.pr_dp_code = Some(read_string(reader, "PrDP code", encoding)?.0).filter(|code| !code.trim().is_empty());
     }
 
-    let (id_country_of_issue, new_index, _) = read_string(&data, index)?;
-    index = new_index;
+    license.id_country_of_issue = read_string(reader, "ID country of issue", encoding)?.0.into();
+    check_country(license, "id_country_of_issue", license.id_country_of_issue.code().to_string());
 
-    let (license_country_of_issue, new_index, _) = read_string(&data, index)?;
-    index = new_index;
+    license.license_country_of_issue = read_string(reader, "license country of issue", encoding)?.0.into();
+    check_country(license, "license_country_of_issue", license.license_country_of_issue.code().to_string());
 
-    let (vehicle_restrictions, new_index) = read_strings(&data, index, 3)?;
-    index = new_index;
+    let vehicle_restrictions = read_strings(reader, 3, encoding);
+    license.vehicle_restrictions = non_empty(&vehicle_restrictions);
 
-    let (license_number, new_index, _) = read_string(&data, index)?;
-    index = new_index;
+    license.license_number = read_string(reader, "license number", encoding)?.0;
 
-    let mut id_number = String::new();
-    for _ in 0..13 {
-        if index < data.len() {
-            id_number.push(data[index] as char);
-            index += 1;
-        } else {
-            return Err("Data ended prematurely while reading ID number".into());
-        }
-    }
+    read_id_number(reader, license, encoding)?;
+
+    Ok((vehicle_codes, vehicle_restrictions))
+}
+
+fn read_id_number(reader: &mut Reader, license: &mut DriversLicenseData, encoding: TextEncoding) -> Result<(), DecodeError> {
+    license.id_number = encoding.decode(reader.take_bytes(ID_NUMBER_LENGTH, "ID number")?);
+
+    let id_number_type = reader.take_byte("ID number type")?;
+    license.id_number_type = format!("{:02}", id_number_type);
+    license.id_document_type = IdDocumentType::from_code(id_number_type);
+    Ok(())
+}
+
+// 13 ASCII digits followed by the ID document type 01 or 02 is distinctive enough to resynchronise on after a
+// garbled RSA block. Foreign ID numbers aren't always digits, so those payloads can't be resynchronised.
+fn find_id_number(data: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?
+        .windows(ID_NUMBER_LENGTH + 1)
+        .position(|window| {
+            window[..ID_NUMBER_LENGTH].iter().all(u8::is_ascii_digit) && matches!(window[ID_NUMBER_LENGTH], 1 | 2)
+        })
+        .map(|position| from + position)
+}
 
-    let id_number_type = format!("{:02}", data[index]);
-    index += 1;
+// Codes, restrictions and issue dates share slot positions, so pair them before empty slots are dropped.
+fn pair_license_codes(codes: &[String], restrictions: &[String], issue_dates: &[Option<LicenseDate>]) -> Vec<LicenseCode> {
+    codes
+        .iter()
+        .enumerate()
+        .filter(|(_, code)| !code.is_empty())
+        .map(|(slot, code)| LicenseCode {
+            code: code.clone(),
+            issued: issue_dates.get(slot).copied().flatten().map(|date| date.to_string().into()),
+            restriction: restrictions.get(slot).filter(|restriction| !restriction.is_empty()).cloned(),
+        })
+        .collect()
+}
+
+fn check_country(license: &mut DriversLicenseData, field: &str, code: String) {
+    if !code.trim().is_empty() && country::country_name(&code).is_none() {
+        license.warnings.push(Warning::for_field("UNKNOWN_COUNTRY_CODE", field, format!("Unrecognised country code {}", code)));
+    }
+}
 
-    let mut nibble_queue = Vec::new();
-    while index < data.len() {
-        let current_byte = data[index];
-        index += 1;
-        if current_byte == 0x57 {
-            break;
+// Everything in the binary section after the licence code issue dates.
+fn parse_binary_section(nibbles: &mut NibbleReader, license: &mut DriversLicenseData) -> Result<(), DecodeError> {
+    let restriction_codes = [nibbles.take("driver restriction codes")?, nibbles.take("driver restriction codes")?];
+    license.driver_restriction_codes = format!("{}{}", restriction_codes[0], restriction_codes[1]);
+    license.driver_restrictions = restriction_codes.into_iter().filter_map(DriverRestriction::from_code).collect();
+    for restriction in &license.driver_restrictions {
+        if let DriverRestriction::Unknown(code) = restriction {
+            license.warnings.push(Warning::for_field(
+                "UNKNOWN_RESTRICTION_CODE",
+                "driver_restrictions",
+                format!("Unrecognised driver restriction code {}", code),
+            ));
         }
-        nibble_queue.push(current_byte >> 4);
-        nibble_queue.push(current_byte & 0x0F);
     }
 
-    let license_code_issue_dates = read_nibble_date_list(&mut nibble_queue, 4);
+    license.prd_permit_expiry_date = Some(read_nibble_date_string(nibbles, "PrDP expiry date")?).filter(|s| !s.is_empty()).map(DateValue::from);
+    license.prdp = ProfessionalPermit::new(license.pr_dp_code.as_deref(), license.prd_permit_expiry_date.clone());
 
-    let driver_restriction_codes = format!("{}{}", nibble_queue.remove(0), nibble_queue.remove(0));
+    license.license_issue_number = read_nibble_digits(nibbles, "license issue number")?;
 
-    let prd_permit_expiry_date = Some(read_nibble_date_string(&mut nibble_queue)).filter(|s| !s.is_empty());
+    license.birthdate = read_nibble_date_string(nibbles, "birthdate")?.into();
 
-    let license_issue_number = format!("{}{}", nibble_queue.remove(0), nibble_queue.remove(0));
+    license.license_issue_date = read_nibble_date_string(nibbles, "license issue date")?.into();
 
-    let birthdate = read_nibble_date_string(&mut nibble_queue);
+    license.license_expiry_date = read_nibble_date_string(nibbles, "license expiry date")?.into();
 
-    let license_issue_date = read_nibble_date_string(&mut nibble_queue);
+    let gender_code = read_nibble_digits(nibbles, "gender")?;
 
-    let license_expiry_date = read_nibble_date_string(&mut nibble_queue);
+    let gender = Gender::from_code(&gender_code);
+    if gender == Gender::Unknown {
+        license.warnings.push(Warning::for_field("UNKNOWN_GENDER_CODE", "gender", format!("Unrecognised gender code {}", gender_code)));
+    }
+    license.gender = GenderValue::new(gender, GenderFormat::Text);
 
-    let gender_code = format!("{}{}", nibble_queue.remove(0), nibble_queue.remove(0));
+    license.id_number_validation = id_number::validate(&license.id_number, license.birthdate.as_date(), gender);
 
-    let gender = if gender_code == "01" { "male".to_string() } else { "female".to_string() };
+    check_issue_ages(license);
 
-    index += 3;
-    let image_width = data[index];
-    index += 2;
-    let image_height = data[index];
+    Ok(())
+}
 
-    Ok(DriversLicenseData {
-        vehicle_codes,
-        surname,
-        initials,
-        pr_dp_code,
-        id_country_of_issue,
-        license_country_of_issue,
-        vehicle_restrictions,
-        license_number,
-        id_number,
-        id_number_type,
-        license_code_issue_dates,
-        driver_restriction_codes,
-        prd_permit_expiry_date,
-        license_issue_number,
-        birthdate,
-        license_issue_date,
-        license_expiry_date,
-        gender,
-        image_width,
-        image_height,
-    })
+// Barcodes edited by hand often get the holder's age at issue wrong, e.g. code A1 issued before 16.
+fn check_issue_ages(license: &mut DriversLicenseData) {
+    let problems = issue_age_problems(license);
+    license.warnings.extend(problems.into_iter().map(|problem| Warning::for_field("ISSUE_AGE", "licenses", problem)));
 }
 
-fn read_strings(data: &[u8], mut index: usize, length: usize) -> Result<(Vec<String>, usize), Box<dyn Error>> {
+pub(crate) fn issue_age_problems(license: &DriversLicenseData) -> Vec<String> {
+    let Some(birthdate) = license.birthdate.as_date().filter(LicenseDate::is_plausible) else {
+        return Vec::new();
+    };
+
+    license
+        .licenses
+        .iter()
+        .filter_map(|code| {
+            let issued = code.issued.as_ref().and_then(DateValue::as_date).filter(LicenseDate::is_plausible)?;
+            let minimum_age = license_code::minimum_age(&code.code)?;
+            let age = birthdate.years_until(issued);
+            (age < minimum_age as i32).then(|| {
+                format!("Licence code {} was issued at age {}, below the minimum of {}", code.code, age, minimum_age)
+            })
+        })
+        .collect()
+}
+
+fn parse_image_section(reader: &mut Reader, license: &mut DriversLicenseData) -> Result<(), DecodeError> {
+    reader.skip(3);
+    license.image_width = reader.take_byte("image width")?;
+    reader.skip(1);
+    license.image_height = reader.take_byte("image height")?;
+    license.image = Some(reader.remaining().to_vec());
+
+    Ok(())
+}
+
+const STRING_DELIMITERS: [u8; 2] = [0xe0, 0xe1];
+
+// Returns one entry per slot read, including empty ones, so slot positions line up across sections.
+fn read_strings(reader: &mut Reader, length: usize, encoding: TextEncoding) -> Vec<String> {
     let mut strings = Vec::with_capacity(length);
 
     for _ in 0..length {
-        let mut string = String::new();
-        loop {
-            match data.get(index) {
-                Some(&b) if b == 0xe0 || b == 0xe1 => {
-                    index += 1;
-                    if !string.is_empty() {
-                        strings.push(string);
-                    }
-                    break;
-                },
-                Some(&b) => {
-                    string.push(b as char);
-                    index += 1;
-                },
-                None => {
-                    if !string.is_empty() {
-                        strings.push(string);
-                    }
-                    return Ok((strings, index));
+        match reader.read_until_delimiter_or_end(&STRING_DELIMITERS) {
+            (bytes, Some(_)) => strings.push(encoding.decode(bytes)),
+            (bytes, None) => {
+                if !bytes.is_empty() {
+                    strings.push(encoding.decode(bytes));
                 }
+                break;
             }
         }
     }
 
-    Ok((strings, index))
+    strings
 }
 
-fn read_string(data: &[u8], mut index: usize) -> Result<(String, usize, u8), Box<dyn Error>> {
-    let mut string = String::new();
-    loop {
-        match data.get(index) {
-            Some(&b) if b == 0xe0 || b == 0xe1 => {
-                let delimiter = b;
-                index += 1;
-                return Ok((string, index, delimiter));
-            },
-            Some(&b) => {
-                string.push(b as char);
-                index += 1;
-            },
-            None => return Err("Unexpected end of data while reading string".into()),
+fn read_string(reader: &mut Reader, field: &'static str, encoding: TextEncoding) -> Result<(String, u8), DecodeError> {
+    let (bytes, delimiter) = reader.read_until_delimiter(&STRING_DELIMITERS, field)?;
+    Ok((encoding.decode(bytes), delimiter))
+}
+
+/// Splits initials such as "JPD", "J P D" or "J.P.D." into `["J", "P", "D"]`.
+pub fn split_initials(initials: &str) -> Vec<String> {
+    initials.chars().filter(|c| c.is_alphabetic()).map(|c| c.to_string()).collect()
+}
+
+pub fn read_nibble_date_list(nibbles: &mut NibbleReader, length: usize, field: &'static str) -> Result<Vec<String>, DecodeError> {
+    let date_slots = read_nibble_date_slots(nibbles, length, field)?;
+    Ok(date_slots.iter().flatten().map(LicenseDate::to_string).collect())
+}
+
+fn read_nibble_date_slots(nibbles: &mut NibbleReader, length: usize, field: &'static str) -> Result<Vec<Option<LicenseDate>>, DecodeError> {
+    (0..length).map(|_| read_nibble_date(nibbles, field)).collect()
+}
+
+fn non_empty(slots: &[String]) -> Vec<String> {
+    slots.iter().filter(|slot| !slot.is_empty()).cloned().collect()
+}
+
+// Two nibbles read as a two digit code, e.g. "01".
+fn read_nibble_digits(nibbles: &mut NibbleReader, field: &'static str) -> Result<String, DecodeError> {
+    Ok(format!("{}{}", nibbles.take(field)?, nibbles.take(field)?))
+}
+
+fn read_nibble_date_string(nibbles: &mut NibbleReader, field: &'static str) -> Result<String, DecodeError> {
+    Ok(read_nibble_date(nibbles, field)?.map(|date| date.to_string()).unwrap_or_default())
+}
+
+// A date is packed as eight BCD nibbles (Y Y Y Y M M D D). An empty slot is a single 0xA nibble.
+fn read_nibble_date(nibbles: &mut NibbleReader, field: &'static str) -> Result<Option<LicenseDate>, DecodeError> {
+    let m = nibbles.take(field)?;
+    if m == EMPTY_DATE_NIBBLE {
+        return Ok(None);
+    }
+
+    let c = nibbles.take(field)?;
+    let d = nibbles.take(field)?;
+    let y = nibbles.take(field)?;
+
+    let m1 = nibbles.take(field)?;
+    let m2 = nibbles.take(field)?;
+
+    let d1 = nibbles.take(field)?;
+    let d2 = nibbles.take(field)?;
+
+    let year = m as u16 * 1000 + c as u16 * 100 + d as u16 * 10 + y as u16;
+    let month = m1 * 10 + m2;
+    let day = d1 * 10 + d2;
+
+    Ok(Some(LicenseDate::new(year, month, day)))
+}
+
+#[cfg(any(test, feature = "test-keys"))]
+pub(crate) fn write_nibble_date(date: &LicenseDate) -> Vec<u8> {
+    let year = date.year;
+    vec![
+        (year / 1000 % 10) as u8,
+        (year / 100 % 10) as u8,
+        (year / 10 % 10) as u8,
+        (year % 10) as u8,
+        date.month / 10,
+        date.month % 10,
+        date.day / 10,
+        date.day % 10,
+    ]
+}
+
+/// An RSA key pair as hex strings. The private exponent signs generated payloads and the public one decodes them.
+#[cfg(feature = "test-keys")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyPairHex {
+    pub modulus: String,
+    pub public_exponent: String,
+    pub private_exponent: String,
+}
+
+/// Key pairs for generating payloads with `header`, e.g. synthetic cards for QA.
+#[cfg(feature = "test-keys")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct SigningKeys {
+    pub header: [u8; 4],
+    pub key_128: KeyPairHex,
+    pub key_74: KeyPairHex,
+}
+
+#[cfg(feature = "test-keys")]
+impl SigningKeys {
+    /// The public keys, to pass in `ParseOptions::keys` when decoding the generated payloads.
+    pub fn version_keys(&self) -> VersionKeys {
+        let public = |key: &KeyPairHex| PublicKeyHex { modulus: key.modulus.clone(), exponent: key.public_exponent.clone() };
+        VersionKeys { header: self.header, key_128: public(&self.key_128), key_74: public(&self.key_74) }
+    }
+}
+
+// A key set no real card uses, so generated fixtures can never pass for a genuine licence.
+#[cfg(feature = "test-keys")]
+const TEST_HEADER: [u8; 4] = [0x01, 0x00, 0x00, 0x45];
+
+#[cfg(feature = "test-keys")]
+const TEST_KEYS_HEX: [(&str, &str, &str); 2] = [
+    (
+        "00d0f4ccf58e283094e4669df2cd5a5a285bcc6ae1a13ca789966fbb902c57b39db71cdc9a295ff51f6918d58cb5fc982f5353747ee9101958929ca5f415b9c75ba266152ce0b960e8496a94ce63c156a1c5e730006bed6f4402d7bdc08399601d9a8420e9b4804614cf6160fbc829cfa92ecb6d75ba47d9186cab4f9265b7ed33",
+        "010001",
+        "7656edecf3c19721fbe891f37c917b15f8476110bfbc163639d8b5a50537f625901aec64f2a1c1b53454383d24a4159c57d89fd5703109e9bbbeb3cdf4afee52d534672dda5660b0c921e2ba8810cc657c495d9d8b1989f08524353e8c155bfedc882f0a586cfbc148cb8079fa218088412b53f928ecafc0573025e4b2278041",
+    ),
+    (
+        "00db6347459f3c0370f2bb394fa76c330efa2cdc20199a78eb68b20266865b1e9b8535090d459c976177fed22ee0e612e07e9dc0c8c7a4922d8ca2a7387d651cc13eac877025144173b613",
+        "010001",
+        "00bea5544f2d23e2bda94187792fbd77d54113f0aa3875a334b70351d0b45ac48b5343963d1fafe431a6ba8fb111eed31fe3f238c8cee963191869ca27bb54194179d72116ef5e99348669",
+    ),
+];
+
+/// The built-in test key set. Decode its payloads with `test_keys().version_keys()`.
+#[cfg(feature = "test-keys")]
+pub fn test_keys() -> SigningKeys {
+    let key = |(modulus, public_exponent, private_exponent): (&str, &str, &str)| KeyPairHex {
+        modulus: modulus.to_string(),
+        public_exponent: public_exponent.to_string(),
+        private_exponent: private_exponent.to_string(),
+    };
+    SigningKeys { header: TEST_HEADER, key_128: key(TEST_KEYS_HEX[0]), key_74: key(TEST_KEYS_HEX[1]) }
+}
+
+// Five 128-byte blocks followed by one 74-byte block after the six byte header, less each block's pad byte.
+#[cfg(feature = "test-keys")]
+const DECRYPTED_LENGTH: usize = 5 * 127 + 73;
+#[cfg(feature = "test-keys")]
+const FILLER: u8 = 0x01;
+
+/// Builds a 720-byte payload that decodes back to `data` with `keys.version_keys()`.
+///
+/// The licence codes, restrictions and issue dates are written from `vehicle_codes`, `vehicle_restrictions`
+/// and `license_code_issue_dates` by position, and the gender from `gender`. Derived fields such as `licenses`
+/// and `prdp` are rebuilt by the decoder. Without an `image`, the photo section is filled with placeholder bytes.
+#[cfg(feature = "test-keys")]
+pub fn encode(data: &DriversLicenseData, keys: &SigningKeys) -> Result<Vec<u8>, DecodeError> {
+    // Raising to the private exponent is the inverse of the decoder's modpow with the public one.
+    let sk_128 = PublicKey::from_hex("signing_128", &keys.key_128.modulus, &keys.key_128.private_exponent)?;
+    let sk_74 = PublicKey::from_hex("signing_74", &keys.key_74.modulus, &keys.key_74.private_exponent)?;
+
+    let plaintext = encode_fields(data)?;
+
+    let mut payload = Vec::with_capacity(LICENSE_LENGTH);
+    payload.extend_from_slice(&keys.header);
+    payload.extend_from_slice(&[0x00, 0x00]);
+    for (index, data) in plaintext.chunks(127).enumerate() {
+        let key = if index < 5 { &sk_128 } else { &sk_74 };
+        let mut block = vec![BLOCK_PAD];
+        block.extend_from_slice(data);
+        payload.extend(encrypt_block(&block, key)?);
+    }
+
+    Ok(payload)
+}
+
+// The decrypted layout: filler up to the string section marker, then the sections `parse_fields` reads.
+#[cfg(feature = "test-keys")]
+fn encode_fields(data: &DriversLicenseData) -> Result<Vec<u8>, DecodeError> {
+    let mut fields = vec![STRING_SECTION_MARKER, 0x00];
+    encode_string_section(data, &mut fields)?;
+
+    let nibbles = pack_nibbles(&encode_binary_section(data)?);
+    if nibbles.contains(&NIBBLE_SECTION_TERMINATOR) {
+        return Err(DecodeError::EncodeFailed("the binary section would contain its own terminator byte".to_string()));
+    }
+    fields.extend(nibbles);
+    fields.push(NIBBLE_SECTION_TERMINATOR);
+
+    fields.extend_from_slice(&[0x00, 0x00, 0x00, data.image_width, 0x00, data.image_height]);
+    let image_length = data.image.as_ref().map_or(DECRYPTED_LENGTH.saturating_sub(fields.len() + 1), Vec::len);
+
+    let padding = DECRYPTED_LENGTH
+        .checked_sub(fields.len() + image_length)
+        .ok_or_else(|| DecodeError::EncodeFailed("the fields and image don't fit in the payload".to_string()))?;
+
+    let mut plaintext = vec![FILLER; padding];
+    plaintext.extend(fields);
+    match &data.image {
+        Some(image) => plaintext.extend_from_slice(image),
+        None => plaintext.resize(DECRYPTED_LENGTH, FILLER),
+    }
+    Ok(plaintext)
+}
+
+#[cfg(feature = "test-keys")]
+fn encode_string_section(data: &DriversLicenseData, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+    for slot in 0..3 {
+        write_string(out, data.vehicle_codes.get(slot).map_or("", String::as_str), 0xe0)?;
+    }
+
+    write_string(out, &data.surname, 0xe0)?;
+
+    match &data.pr_dp_code {
+        Some(code) => {
+            write_string(out, &data.initials, 0xe0)?;
+            write_string(out, code, 0xe0)?;
+        }
+        None => write_string(out, &data.initials, 0xe1)?,
+    }
+
+    write_string(out, data.id_country_of_issue.code(), 0xe0)?;
+    write_string(out, data.license_country_of_issue.code(), 0xe0)?;
+
+    for slot in 0..3 {
+        write_string(out, data.vehicle_restrictions.get(slot).map_or("", String::as_str), 0xe0)?;
+    }
+
+    write_string(out, &data.license_number, 0xe0)?;
+
+    let id_number = encode_text(&data.id_number)?;
+    if id_number.len() != 13 {
+        return Err(DecodeError::EncodeFailed(format!("ID number {} is not 13 characters", data.id_number)));
+    }
+    out.extend(id_number);
+
+    out.push(data.id_number_type.trim().parse().unwrap_or(data.id_document_type.code()));
+
+    Ok(())
+}
+
+// Mirrors `parse_binary_section`, preceded by the four licence code issue date slots.
+#[cfg(feature = "test-keys")]
+fn encode_binary_section(data: &DriversLicenseData) -> Result<Vec<u8>, DecodeError> {
+    let mut nibbles = Vec::new();
+
+    for slot in 0..4 {
+        write_date(&mut nibbles, data.license_code_issue_dates.get(slot), "license_code_issue_dates")?;
+    }
+
+    let restriction_codes = if data.driver_restriction_codes.len() == 2 {
+        data.driver_restriction_codes.clone()
+    } else {
+        let mut codes: String = data.driver_restrictions.iter().map(|restriction| restriction.code().to_string()).collect();
+        while codes.len() < 2 {
+            codes.push('0');
         }
+        codes
+    };
+    write_digits(&mut nibbles, &restriction_codes, "driver_restriction_codes")?;
+
+    write_date(&mut nibbles, data.prd_permit_expiry_date.as_ref(), "prd_permit_expiry_date")?;
+    write_digits(&mut nibbles, &data.license_issue_number, "license_issue_number")?;
+    write_date(&mut nibbles, Some(&data.birthdate), "birthdate")?;
+    write_date(&mut nibbles, Some(&data.license_issue_date), "license_issue_date")?;
+    write_date(&mut nibbles, Some(&data.license_expiry_date), "license_expiry_date")?;
+
+    let gender_code = match data.gender.gender() {
+        Gender::Male => "01",
+        Gender::Female => "02",
+        Gender::Unknown => "00",
+    };
+    write_digits(&mut nibbles, gender_code, "gender")?;
+
+    Ok(nibbles)
+}
+
+#[cfg(feature = "test-keys")]
+fn write_string(out: &mut Vec<u8>, value: &str, delimiter: u8) -> Result<(), DecodeError> {
+    let bytes = encode_text(value)?;
+    if bytes.iter().any(|b| STRING_DELIMITERS.contains(b)) {
+        return Err(DecodeError::EncodeFailed(format!("{} contains a string delimiter byte", value)));
     }
+    out.extend(bytes);
+    out.push(delimiter);
+    Ok(())
 }
 
-pub fn read_nibble_date_list(nibble_queue: &mut Vec<u8>, length: usize) -> Vec<String> {
-    let mut date_list = Vec::new();
+// Latin-1, matching the default `TextEncoding`.
+#[cfg(feature = "test-keys")]
+fn encode_text(value: &str) -> Result<Vec<u8>, DecodeError> {
+    value
+        .chars()
+        .map(|c| u8::try_from(c).map_err(|_| DecodeError::EncodeFailed(format!("{} can't be encoded as Latin-1", value))))
+        .collect()
+}
 
-    for _ in 0..length {
-        let date_string = read_nibble_date_string(nibble_queue);
-        if !date_string.is_empty() {
-            date_list.push(date_string);
+#[cfg(feature = "test-keys")]
+fn write_date(nibbles: &mut Vec<u8>, value: Option<&DateValue>, field: &str) -> Result<(), DecodeError> {
+    let Some(value) = value.filter(|value| !value.is_empty()) else {
+        nibbles.push(EMPTY_DATE_NIBBLE);
+        return Ok(());
+    };
+    let date_nibbles = value.as_date().map(|date| write_nibble_date(&date)).filter(|digits| digits.iter().all(|&d| d <= 9));
+    nibbles.extend(date_nibbles.ok_or_else(|| DecodeError::EncodeFailed(format!("Field {} has an unreadable date {}", field, value)))?);
+    Ok(())
+}
+
+#[cfg(feature = "test-keys")]
+fn write_digits(nibbles: &mut Vec<u8>, value: &str, field: &str) -> Result<(), DecodeError> {
+    let digits: Option<Vec<u8>> = value.chars().map(|c| c.to_digit(10).map(|d| d as u8)).collect();
+    match digits {
+        Some(digits) if digits.len() == 2 => {
+            nibbles.extend(digits);
+            Ok(())
         }
+        _ => Err(DecodeError::EncodeFailed(format!("Field {} must be two digits, not {}", field, value))),
     }
+}
 
-    date_list
+// High nibble first, padding an odd count with a zero nibble.
+#[cfg(feature = "test-keys")]
+fn pack_nibbles(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)).collect()
 }
 
-fn read_nibble_date_string(nibble_queue: &mut Vec<u8>) -> String {
-    let m = nibble_queue.remove(0);
-    if m == 10 {
-        return String::new();
+#[cfg(feature = "test-keys")]
+fn encrypt_block(block: &[u8], key: &PublicKey) -> Result<Vec<u8>, DecodeError> {
+    // RSA only round-trips values below the modulus, which the zero pad byte guarantees for a full-length key.
+    let value = BigUint::from_bytes_be(block);
+    if value >= key.n {
+        return Err(DecodeError::EncodeFailed("the key modulus is shorter than its block".to_string()));
     }
 
-    let c = nibble_queue.remove(0);
-    let d = nibble_queue.remove(0);
-    let y = nibble_queue.remove(0);
+    let encrypted = value.modpow(&key.e, &key.n).to_bytes_be();
+    let padding = block
+        .len()
+        .checked_sub(encrypted.len())
+        .ok_or_else(|| DecodeError::EncodeFailed("the key modulus is longer than its block".to_string()))?;
+
+    let mut out = vec![0; padding];
+    out.extend(encrypted);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::Reader;
+    use proptest::prelude::*;
 
-    let m1 = nibble_queue.remove(0);
-    let m2 = nibble_queue.remove(0);
+    fn valid_date() -> impl Strategy<Value = LicenseDate> {
+        (1900u16..=2100, 1u8..=12, 1u8..=31)
+            .prop_map(|(year, month, day)| LicenseDate::new(year, month, day))
+            .prop_filter("a real calendar date", LicenseDate::is_plausible)
+    }
+
+    proptest! {
+        #[test]
+        fn nibble_dates_round_trip(date in valid_date()) {
+            let packed: Vec<u8> = write_nibble_date(&date).chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect();
+            // 0xff can't occur in BCD digits, so the whole date is read.
+            let mut nibbles = Reader::new(&packed).read_nibbles(0xff);
+            prop_assert_eq!(read_nibble_date(&mut nibbles, "date")?, Some(date));
+        }
+    }
+
+    // A licence in the shape the decoder returns, including the fields it derives.
+    fn license(surname: &str, initials: &str) -> DriversLicenseData {
+        let date = |text: &str| DateValue::from(text.to_string());
+        let birthdate = LicenseDate::new(1985, 3, 14);
+        let id_number = "8503145800089".to_string();
+        let issue_dates = vec![date("2005/06/01"), date("2012/09/20")];
+        DriversLicenseData {
+            licenses: vec![
+                LicenseCode { code: "B".to_string(), issued: Some(date("2005/06/01")), restriction: Some("0".to_string()) },
+                LicenseCode { code: "EB".to_string(), issued: Some(date("2012/09/20")), restriction: Some("1".to_string()) },
+            ],
+            vehicle_codes: vec!["B".to_string(), "EB".to_string()],
+            surname: surname.to_string(),
+            initials: initials.to_string(),
+            initials_list: split_initials(initials),
+            id_country_of_issue: "ZA".to_string().into(),
+            license_country_of_issue: "ZA".to_string().into(),
+            vehicle_restrictions: vec!["0".to_string(), "1".to_string()],
+            license_number: "10250000ABCD".to_string(),
+            id_number_validation: id_number::validate(&id_number, Some(birthdate), Gender::Male),
+            id_number,
+            id_number_type: "02".to_string(),
+            id_document_type: IdDocumentType::from_code(2),
+            license_code_issue_dates: issue_dates,
+            driver_restriction_codes: "00".to_string(),
+            license_issue_number: "02".to_string(),
+            birthdate: date("1985/03/14"),
+            license_issue_date: date("2021/04/01"),
+            license_expiry_date: date("2026/03/31"),
+            gender: GenderValue::new(Gender::Male, GenderFormat::Text),
+            image_width: 250,
+            image_height: 200,
+            image: Some((0..=255).cycle().take(300).collect()),
+            ..DriversLicenseData::default()
+        }
+    }
 
-    let d1 = nibble_queue.remove(0);
-    let d2 = nibble_queue.remove(0);
+    #[test]
+    fn identity_key_identifies_the_card_but_not_the_scan() {
+        let data = license("SMITH", "JP");
+        assert_eq!(data.identity_key(), "8503145800089|10250000ABCD|02");
 
-    format!("{}{}{}{}/{}{}/{}{}", m, c, d, y, m1, m2, d1, d2)
+        let mut rescanned = data.clone();
+        rescanned.image = Some(vec![0x42; 300]);
+        rescanned.surname = " SMITH ".to_string();
+        rescanned.license_number = " 10250000ABCD".to_string();
+        assert_eq!(rescanned.identity_key(), data.identity_key());
+
+        let mut other_card = data.clone();
+        other_card.license_number = "10250000WXYZ".to_string();
+        assert_ne!(other_card.identity_key(), data.identity_key());
+
+        let mut reissued = data.clone();
+        reissued.license_issue_number = "03".to_string();
+        assert_ne!(reissued.identity_key(), data.identity_key());
+    }
+
+    #[test]
+    fn redaction_masks_identifiers_and_drops_the_birthdate_photo_and_age_warnings() {
+        let mut data = license("SMITH", "JP");
+        let kept = Warning::for_field("IMPLAUSIBLE_DATE", "license_expiry_date", "Implausible date 2026/02/30");
+        data.warnings = vec![
+            Warning::new("ISSUE_AGE", "Licence code B was issued at age 12"),
+            Warning::for_field("IMPLAUSIBLE_DATE", "birthdate", "Implausible date 1985/13/14"),
+            kept.clone(),
+        ];
+        data.redact();
+
+        assert_eq!(data.id_number, "8503*********");
+        assert_eq!(data.license_number, "*********BCD");
+        assert_eq!(data.birthdate, DateValue::default());
+        assert_eq!(data.image, None);
+        assert_eq!(data.warnings, [kept]);
+        assert_eq!((data.surname.as_str(), data.license_issue_number.as_str()), ("SMITH", "02"));
+    }
+
+    #[test]
+    fn block_data_lines_up_with_the_decrypted_values() {
+        // A padded block, a block without a pad byte, and a padded final block.
+        let blocks = vec![vec![0x00, 0x01, 0x82, 0x5a], vec![0x7f, 0x00, 0x03], vec![0x00, 0x57, 0x00]];
+        assert_eq!(join_block_values(&blocks), [0x01, 0x82, 0x5a, 0x7f, 0x00, 0x03, 0x57, 0x00]);
+        assert_eq!(join_block_data(&blocks), (join_block_values(&blocks), vec![3, 6, 8]));
+    }
+
+    #[test]
+    fn block_data_keeps_zero_bytes_after_the_pad() {
+        // `to_bytes_be` drops every leading zero, which would shift all later fields.
+        let blocks = vec![vec![0x00, 0x00, 0x00, 0x82], vec![0x00, 0x41]];
+        assert_eq!(join_block_values(&blocks), [0x82, 0x41]);
+        assert_eq!(join_block_data(&blocks), (vec![0x00, 0x00, 0x82, 0x41], vec![3, 4]));
+    }
+
+    #[cfg(feature = "test-keys")]
+    mod encode {
+        use super::*;
+
+        fn options() -> ParseOptions {
+            ParseOptions { keys: vec![test_keys().version_keys()], include_image: true, ..ParseOptions::default() }
+        }
+
+        fn round_trip(data: &DriversLicenseData) -> DriversLicenseData {
+            let payload = encode(data, &test_keys()).expect("the licence encodes");
+            parse_bytes_with_options(&payload, options()).expect("the payload decodes")
+        }
+
+        #[test]
+        fn encoded_licenses_decode_to_the_same_data() {
+            let data = license("SMITH", "JP");
+            assert_eq!(round_trip(&data), data);
+        }
+
+        #[test]
+        fn high_latin1_names_round_trip() {
+            for surname in ["ÿ".repeat(100), "ÉÇÔÖÜ DU PLESSIS".to_string(), "ÑØÅÆ".to_string()] {
+                let data = license(&surname, "ÀÉ");
+                assert_eq!(round_trip(&data), data, "surname {}", surname);
+            }
+        }
+
+        #[test]
+        fn every_block_boundary_round_trips() {
+            // Shifting the sections a byte at a time puts each delimiter and image header byte on a block boundary.
+            for length in 1..=100 {
+                let data = license(&"Ü".repeat(length), "J");
+                assert_eq!(round_trip(&data), data, "surname of {} characters", length);
+            }
+        }
+
+        #[test]
+        fn zero_bytes_at_block_starts_round_trip() {
+            let mut data = license("SMITH", "JP");
+            data.image = Some(vec![0; 400]);
+            assert_eq!(round_trip(&data), data);
+        }
+
+        #[test]
+        fn redaction_drops_warnings_that_reveal_the_birthdate() {
+            let mut data = license("SMITH", "JP");
+            data.birthdate = DateValue::from("1988/03/14".to_string());
+            let issue_age = parse_bytes_with_options(&encode(&data, &test_keys()).expect("the licence encodes"), options()).expect("the payload decodes");
+            assert!(issue_age.warnings.iter().any(|warning| warning.code == "ISSUE_AGE"));
+
+            data.birthdate = DateValue::from("1985/13/14".to_string());
+            let payload = encode(&data, &test_keys()).expect("the licence encodes");
+            let iso = || ParseOptions { date_format: DateFormat::Iso, ..options() };
+            let implausible = parse_bytes_with_options(&payload, iso()).expect("the payload decodes");
+            assert!(implausible.warnings.iter().any(|warning| warning.field.as_deref() == Some("birthdate")));
+
+            let mut redacted = issue_age;
+            redacted.redact();
+            assert_eq!(redacted.warnings, []);
+            let redacted = parse_bytes_with_options(&payload, ParseOptions { redact: true, ..iso() }).expect("the payload decodes");
+            assert_eq!(redacted.warnings, []);
+        }
+    }
 }
@@ -0,0 +1,213 @@
+use std::error::Error;
+use std::fmt;
+
+
+/// The part of a driver's licence payload that was being read when decoding failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// The version header and RSA blocks, before anything is decrypted.
+    Header,
+    /// The 0xe0/0xe1-delimited names, codes and numbers.
+    Strings,
+    /// The nibble-packed dates, restriction codes and gender.
+    Nibbles,
+    /// The photo dimensions in front of the image data.
+    ImageHeader,
+}
+
+impl Section {
+    /// A stable identifier, e.g. "nibbles".
+    pub fn id(&self) -> &'static str {
+        match self {
+            Section::Header => "header",
+            Section::Strings => "strings",
+            Section::Nibbles => "nibbles",
+            Section::ImageHeader => "image_header",
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Header => write!(f, "header"),
+            Section::Strings => write!(f, "string section"),
+            Section::Nibbles => write!(f, "nibble section"),
+            Section::ImageHeader => write!(f, "image header"),
+        }
+    }
+}
+
+// Flat on the foreign side: Kotlin and Swift see the variant and its message, not the inner errors.
+#[derive(Debug)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
+pub enum DecodeError {
+    InsufficientBytes,
+    UnknownVersion,
+    UnknownKey(String),
+    InvalidKey(String),
+    DecryptFailed,
+    /// `rsa_block` is set once the offset has been placed within the decrypted blocks.
+    TruncatedData { section: Section, field: &'static str, offset: usize, rsa_block: Option<usize> },
+    MissingImage,
+    InvalidUtf8(std::str::Utf8Error),
+    InvalidBase64(base64::DecodeError),
+    InvalidHex,
+    InsufficientParts,
+    UnrecognizedFormat,
+    InvalidOptions(String),
+    SerializationFailed(String),
+    EncodeFailed(String),
+    IncompleteSegments { missing: Vec<u32>, segment_count: Option<u32> },
+    SegmentMismatch(String),
+}
+
+impl DecodeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DecodeError::InsufficientBytes => "INSUFFICIENT_BYTES",
+            DecodeError::UnknownVersion => "UNKNOWN_VERSION",
+            DecodeError::UnknownKey(_) => "UNKNOWN_KEY",
+            DecodeError::InvalidKey(_) => "INVALID_KEY",
+            DecodeError::DecryptFailed => "DECRYPT_FAILED",
+            DecodeError::TruncatedData { .. } => "TRUNCATED_DATA",
+            DecodeError::MissingImage => "MISSING_IMAGE",
+            DecodeError::InvalidUtf8(_) => "INVALID_UTF8",
+            DecodeError::InvalidBase64(_) => "INVALID_BASE64",
+            DecodeError::InvalidHex => "INVALID_HEX",
+            DecodeError::InsufficientParts => "INSUFFICIENT_PARTS",
+            DecodeError::UnrecognizedFormat => "UNRECOGNIZED_FORMAT",
+            DecodeError::InvalidOptions(_) => "INVALID_OPTIONS",
+            DecodeError::SerializationFailed(_) => "SERIALIZATION_FAILED",
+            DecodeError::EncodeFailed(_) => "ENCODE_FAILED",
+            DecodeError::IncompleteSegments { .. } => "INCOMPLETE_SEGMENTS",
+            DecodeError::SegmentMismatch(_) => "SEGMENT_MISMATCH",
+        }
+    }
+
+    /// The payload section being read when decoding failed, when known.
+    pub fn section(&self) -> Option<Section> {
+        match self {
+            DecodeError::InsufficientBytes | DecodeError::UnknownVersion | DecodeError::DecryptFailed => Some(Section::Header),
+            DecodeError::TruncatedData { section, .. } => Some(*section),
+            _ => None,
+        }
+    }
+
+    /// The field being read when decoding failed, e.g. "birthdate".
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            DecodeError::TruncatedData { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// The RSA block, from 1 to 6, that the byte where decoding stopped was decrypted from.
+    pub fn rsa_block(&self) -> Option<usize> {
+        match self {
+            DecodeError::TruncatedData { rsa_block, .. } => *rsa_block,
+            _ => None,
+        }
+    }
+
+    /// Places the offset within the decrypted blocks, given the offset where each block's data ends.
+    pub(crate) fn in_blocks(self, block_ends: &[usize]) -> Self {
+        match self {
+            DecodeError::TruncatedData { section, field, offset, .. } => {
+                let block = block_ends.iter().position(|&end| offset < end).or(block_ends.len().checked_sub(1));
+                DecodeError::TruncatedData { section, field, offset, rsa_block: block.map(|index| index + 1) }
+            }
+            err => err,
+        }
+    }
+
+    /// Byte offset into the decrypted payload, which leaves out each block's pad byte, where decoding stopped, when known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DecodeError::TruncatedData { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InsufficientBytes => write!(f, "Invalid license (insufficient bytes)"),
+            DecodeError::UnknownVersion => write!(f, "Unrecognized license version"),
+            DecodeError::UnknownKey(name) => write!(f, "Unknown key name {}", name),
+            DecodeError::InvalidKey(name) => write!(f, "Failed to parse public key {}", name),
+            DecodeError::DecryptFailed => write!(f, "Encrypted payload is too short to decrypt"),
+            DecodeError::TruncatedData { section, field, offset, rsa_block } => {
+                write!(f, "Data ended prematurely while reading {} in the {} at byte {}", field, section, offset)?;
+                match rsa_block {
+                    Some(rsa_block) => write!(f, " (RSA block {})", rsa_block),
+                    None => Ok(()),
+                }
+            }
+            DecodeError::MissingImage => write!(f, "License does not contain an image section"),
+            DecodeError::InvalidUtf8(err) => write!(f, "Invalid UTF-8: {}", err),
+            DecodeError::InvalidBase64(err) => write!(f, "Invalid base64: {}", err),
+            DecodeError::InvalidHex => write!(f, "Invalid hex input"),
+            DecodeError::InsufficientParts => write!(f, "Input data does not contain enough parts"),
+            DecodeError::UnrecognizedFormat => write!(f, "Unrecognized license format"),
+            DecodeError::InvalidOptions(reason) => write!(f, "Invalid options: {}", reason),
+            DecodeError::SerializationFailed(reason) => write!(f, "Failed to serialize the result: {}", reason),
+            DecodeError::EncodeFailed(reason) => write!(f, "Failed to encode the license: {}", reason),
+            DecodeError::IncompleteSegments { missing, segment_count: None } if missing.is_empty() => {
+                write!(f, "Waiting for the last Macro PDF417 segment")
+            }
+            DecodeError::IncompleteSegments { missing, .. } => write!(f, "Missing Macro PDF417 segments {:?}", missing),
+            DecodeError::SegmentMismatch(reason) => write!(f, "Inconsistent Macro PDF417 segment: {}", reason),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::InvalidUtf8(err) => Some(err),
+            DecodeError::InvalidBase64(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for DecodeError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        DecodeError::InvalidUtf8(err)
+    }
+}
+
+impl From<base64::DecodeError> for DecodeError {
+    fn from(err: base64::DecodeError) -> Self {
+        DecodeError::InvalidBase64(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn truncated_at(offset: usize) -> DecodeError {
+        DecodeError::TruncatedData { section: Section::ImageHeader, field: "image height", offset, rsa_block: None }
+    }
+
+    #[test]
+    fn rsa_block_follows_the_block_boundaries() {
+        // Blocks of 127 data bytes after their pad byte, except the second, which has none.
+        let block_ends = [127, 255, 382, 509, 636, 709];
+        let blocks: Vec<_> = [0, 126, 127, 254, 255, 635, 636, 708, 720]
+            .into_iter()
+            .map(|offset| truncated_at(offset).in_blocks(&block_ends).rsa_block())
+            .collect();
+        assert_eq!(blocks, [1, 1, 2, 2, 3, 5, 6, 6, 6].map(Some));
+        assert_eq!(truncated_at(0).rsa_block(), None);
+    }
+
+    #[test]
+    fn messages_only_name_a_known_rsa_block() {
+        assert_eq!(truncated_at(300).to_string(), "Data ended prematurely while reading image height in the image header at byte 300");
+        assert!(truncated_at(300).in_blocks(&[127, 255, 382]).to_string().ends_with("at byte 300 (RSA block 3)"));
+    }
+}
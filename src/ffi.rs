@@ -0,0 +1,107 @@
+use std::ffi::{CString, c_char};
+use serde::Serialize;
+use crate::error::DecodeError;
+use crate::output;
+
+/// The call succeeded and `out_json` holds the parsed data.
+pub const LDC_OK: i32 = 0;
+/// Decoding failed and `out_json` holds `{ code, message, section?, field?, offset?, rsa_block? }`.
+pub const LDC_DECODE_ERROR: i32 = 1;
+/// A required pointer was null. `out_json` is left untouched.
+pub const LDC_INVALID_ARGUMENT: i32 = 2;
+
+#[derive(Serialize)]
+struct FfiError {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rsa_block: Option<usize>,
+}
+
+/// Parses a 720-byte driver's licence payload into a JSON string.
+///
+/// # Safety
+/// `ptr` must point to `len` readable bytes and `out_json` must be a valid pointer to write to.
+/// The string written to `out_json` must be released with `ldc_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ldc_parse_drivers_license(ptr: *const u8, len: usize, out_json: *mut *mut c_char) -> i32 {
+    // SAFETY: the caller upholds the contract documented above.
+    unsafe { parse_with(ptr, len, out_json, |bytes| output::to_json_string(&crate::parse_drivers_license(bytes)?, false)) }
+}
+
+/// Parses a vehicle licence disc payload into a JSON string.
+///
+/// # Safety
+/// Same contract as `ldc_parse_drivers_license`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ldc_parse_vehicle_license(ptr: *const u8, len: usize, out_json: *mut *mut c_char) -> i32 {
+    // SAFETY: the caller upholds the contract documented above.
+    unsafe { parse_with(ptr, len, out_json, |bytes| output::to_json_string(&crate::parse_vehicle_license(bytes)?, false)) }
+}
+
+/// Detects the payload type and parses it into `{ "type": "drivers" | "vehicle" | "smart_id", "data": ... }`.
+///
+/// # Safety
+/// Same contract as `ldc_parse_drivers_license`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ldc_parse_license(ptr: *const u8, len: usize, out_json: *mut *mut c_char) -> i32 {
+    // SAFETY: the caller upholds the contract documented above.
+    unsafe { parse_with(ptr, len, out_json, |bytes| output::to_json_string(&crate::parse_license(bytes)?, false)) }
+}
+
+/// Releases a string returned through `out_json`. Null is ignored.
+///
+/// # Safety
+/// `json` must be null or a string from this library that hasn't been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ldc_free_string(json: *mut c_char) {
+    if !json.is_null() {
+        // SAFETY: the string was created by `CString::into_raw` in `write_json`.
+        drop(unsafe { CString::from_raw(json) });
+    }
+}
+
+unsafe fn parse_with(
+    ptr: *const u8,
+    len: usize,
+    out_json: *mut *mut c_char,
+    parse: impl FnOnce(&[u8]) -> Result<String, DecodeError>,
+) -> i32 {
+    if ptr.is_null() || out_json.is_null() {
+        return LDC_INVALID_ARGUMENT;
+    }
+
+    // SAFETY: `ptr` is non-null and the caller guarantees `len` readable bytes.
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let (status, json) = match parse(bytes) {
+        Ok(json) => (LDC_OK, json),
+        Err(e) => (LDC_DECODE_ERROR, error_json(&e)),
+    };
+
+    // SAFETY: `out_json` is non-null and the caller guarantees it is writable.
+    unsafe { *out_json = write_json(json) };
+    status
+}
+
+fn error_json(error: &DecodeError) -> String {
+    let error = FfiError {
+        code: error.code(),
+        message: error.to_string(),
+        section: error.section().map(|section| section.id()),
+        field: error.field(),
+        offset: error.offset(),
+        rsa_block: error.rsa_block(),
+    };
+    serde_json::to_string(&error).unwrap_or_else(|_| format!("{{\"code\":\"{}\"}}", error.code))
+}
+
+// JSON escapes NUL as \u0000, so the string never contains an interior NUL byte.
+fn write_json(json: String) -> *mut c_char {
+    CString::new(json).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
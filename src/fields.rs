@@ -0,0 +1,149 @@
+use serde::Serialize;
+use crate::license::LicenseFormat;
+
+/// How carefully a UI should treat a field's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "lowercase")]
+pub enum Sensitivity {
+    /// Says nothing about the holder, e.g. a disc number or parse diagnostics.
+    Public,
+    /// About the holder, but not enough to identify them on its own, e.g. the name or licence codes.
+    Personal,
+    /// Identifies the holder: ID and licence numbers, birthdate and photo. `redact` masks or drops these.
+    Identifying,
+}
+
+/// The shape of a field's value with default options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+    /// A string by default, or `{ year, month, day }` with the structured date format.
+    Date,
+    /// "male", "female" or "unknown" by default, or an ISO/IEC 5218 number.
+    Gender,
+    /// An ISO 3166 alpha-2 code, or `{ code, name }` with `expand_countries`.
+    Country,
+    List,
+    Object,
+    Bytes,
+}
+
+/// Metadata about one output field, for rendering verification screens without hard-coding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct FieldDescription {
+    /// The snake_case key in the output.
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    pub description: &'static str,
+    pub sensitivity: Sensitivity,
+    /// A made-up value as it would be displayed, e.g. "SMITH". Empty for fields that can't be shown as text.
+    pub example: &'static str,
+}
+
+/// Every output field of `format`, in output order.
+pub fn describe_fields(format: LicenseFormat) -> &'static [FieldDescription] {
+    match format {
+        LicenseFormat::Drivers => &DRIVERS_FIELDS,
+        LicenseFormat::Vehicle => &VEHICLE_FIELDS,
+        LicenseFormat::SmartId => &SMART_ID_FIELDS,
+    }
+}
+
+const fn field(
+    name: &'static str,
+    field_type: FieldType,
+    sensitivity: Sensitivity,
+    description: &'static str,
+    example: &'static str,
+) -> FieldDescription {
+    FieldDescription { name, field_type, description, sensitivity, example }
+}
+
+use Sensitivity::*;
+
+const DRIVERS_FIELDS: [FieldDescription; 31] = [
+    field("licenses", FieldType::List, Personal, "Each licence code with its issue date and vehicle restriction", "B issued 2010/05/21"),
+    field("vehicle_codes", FieldType::List, Personal, "The licence codes, e.g. B or EC1", "B, EB"),
+    field("surname", FieldType::String, Personal, "The holder's surname", "SMITH"),
+    field("initials", FieldType::String, Personal, "The holder's initials", "JA"),
+    field("initials_list", FieldType::List, Personal, "The initials split into letters", "J, A"),
+    field("pr_dp_code", FieldType::String, Personal, "The professional driving permit categories, if the holder has one", "GP"),
+    field("id_country_of_issue", FieldType::Country, Personal, "The country that issued the holder's ID document", "ZA"),
+    field("license_country_of_issue", FieldType::Country, Personal, "The country that issued the licence", "ZA"),
+    field("vehicle_restrictions", FieldType::List, Personal, "The vehicle restriction for each licence code", "1"),
+    field("license_number", FieldType::String, Identifying, "The licence number printed on the card", "10070000X5NZ"),
+    field("id_number", FieldType::String, Identifying, "The holder's ID or passport number", "8501015800088"),
+    field("id_number_type", FieldType::String, Personal, "The ID document type code, \"02\" for an RSA ID", "02"),
+    field("id_document_type", FieldType::Object, Personal, "The ID document type code and its meaning", "RSA ID document"),
+    field("id_number_validation", FieldType::Object, Public, "Whether the ID number's check digit, birthdate and gender agree", ""),
+    field("license_code_issue_dates", FieldType::List, Personal, "The issue date of each licence code", "2010/05/21"),
+    field("driver_restriction_codes", FieldType::String, Personal, "The two driver restriction digits as read", "10"),
+    field("driver_restrictions", FieldType::List, Personal, "The driver restrictions with their meaning", "corrective lenses"),
+    field("prd_permit_expiry_date", FieldType::Date, Personal, "When the professional driving permit expires", "2026/03/31"),
+    field("prdp", FieldType::Object, Personal, "The professional driving permit categories and expiry date", "G, P until 2026/03/31"),
+    field("license_issue_number", FieldType::String, Public, "How many times the card has been issued", "02"),
+    field("birthdate", FieldType::Date, Identifying, "The holder's date of birth", "1985/01/01"),
+    field("license_issue_date", FieldType::Date, Personal, "When the card was issued", "2021/06/14"),
+    field("license_expiry_date", FieldType::Date, Personal, "When the card expires", "2026/06/13"),
+    field("gender", FieldType::Gender, Personal, "The holder's gender", "male"),
+    field("image_width", FieldType::Number, Public, "The photo width in pixels", "250"),
+    field("image_height", FieldType::Number, Public, "The photo height in pixels", "200"),
+    field("image", FieldType::Bytes, Identifying, "The encoded photo, with the include_image option", ""),
+    field("warnings", FieldType::List, Public, "Conditions that didn't fail the parse, each with a code and message", ""),
+    field("confidence", FieldType::Object, Public, "How far a lenient parse can be trusted", "0.85"),
+    field("payload_sha256", FieldType::String, Public, "A hash of the payload, with the fingerprint option", ""),
+    field("skipped_ranges", FieldType::List, Public, "Payload bytes a lenient parse skipped over", ""),
+];
+
+const VEHICLE_FIELDS: [FieldDescription; 27] = [
+    field("disc_type", FieldType::String, Public, "The disc type code", "MVL1CC14"),
+    field("authority_code", FieldType::String, Public, "The registering authority code", "0154"),
+    field("control_number", FieldType::String, Public, "The disc control number", "4025T0JB"),
+    field("disc_number", FieldType::String, Public, "The disc number", "40250031T0JB"),
+    field("make", FieldType::String, Public, "The vehicle make", "VOLKSWAGEN"),
+    field("model", FieldType::String, Public, "The vehicle model", "POLO"),
+    field("make_model", FieldType::String, Public, "Make and model together", "VOLKSWAGEN POLO"),
+    field("description", FieldType::String, Public, "The vehicle description", "Hatch back / Luikrug"),
+    field("is_trailer", FieldType::Boolean, Public, "Whether the description names a trailer", "false"),
+    field("color", FieldType::String, Public, "The vehicle colour", "White / Wit"),
+    field("license_number", FieldType::String, Personal, "The number plate", "CY123456"),
+    field("license_number_valid", FieldType::Boolean, Public, "Whether the number plate has a known format", "true"),
+    field("province", FieldType::String, Public, "The province the plate was issued in", "Western Cape"),
+    field("registration_authority", FieldType::String, Public, "The registering town, for plates that carry one", "Bellville"),
+    field("vin_number", FieldType::String, Personal, "The vehicle identification number", "AAVZZZ6RZCU012345"),
+    field("vehicle_register_number", FieldType::String, Personal, "The vehicle register number", "ABC123GP"),
+    field("engine_number", FieldType::String, Personal, "The engine number, usually empty for trailers", "CJZ123456"),
+    field("expiry_date", FieldType::Date, Public, "When the disc expires", "2025-07-31"),
+    field("gvm", FieldType::Number, Public, "The gross vehicle mass in kg", "1650"),
+    field("gvm_raw", FieldType::String, Public, "The gross vehicle mass as printed", "1650"),
+    field("tare", FieldType::Number, Public, "The tare mass in kg", "1109"),
+    field("tare_raw", FieldType::String, Public, "The tare mass as printed", "1109"),
+    field("persons_seated", FieldType::Number, Public, "How many people may be seated", "5"),
+    field("persons_standing", FieldType::Number, Public, "How many people may stand", "0"),
+    field("vehicle_category", FieldType::String, Public, "The vehicle category", "Light passenger mv"),
+    field("warnings", FieldType::List, Public, "Conditions that didn't fail the parse, each with a code and message", ""),
+    field("payload_sha256", FieldType::String, Public, "A hash of the payload, with the fingerprint option", ""),
+];
+
+const SMART_ID_FIELDS: [FieldDescription; 13] = [
+    field("surname", FieldType::String, Personal, "The holder's surname", "SMITH"),
+    field("names", FieldType::String, Personal, "The holder's forenames", "JOHN ALBERT"),
+    field("gender", FieldType::Gender, Personal, "The holder's gender", "male"),
+    field("nationality", FieldType::String, Personal, "The holder's nationality, \"RSA\" for citizens", "RSA"),
+    field("id_number", FieldType::String, Identifying, "The holder's ID number", "8501015800088"),
+    field("id_number_validation", FieldType::Object, Public, "Whether the ID number's check digit, birthdate and gender agree", ""),
+    field("birthdate", FieldType::Date, Identifying, "The holder's date of birth", "01 JAN 1985"),
+    field("country_of_birth", FieldType::String, Personal, "The holder's country of birth", "RSA"),
+    field("citizenship_status", FieldType::String, Personal, "e.g. CITIZEN or PERMANENT RESIDENT", "CITIZEN"),
+    field("issue_date", FieldType::Date, Public, "When the card was issued", "14 JUN 2021"),
+    field("extra_fields", FieldType::List, Public, "Any undocumented fields after the issue date", ""),
+    field("warnings", FieldType::List, Public, "Conditions that didn't fail the parse, each with a code and message", ""),
+    field("payload_sha256", FieldType::String, Public, "A hash of the payload, with the fingerprint option", ""),
+];
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gender {
+    Male,
+    Female,
+    Unknown,
+}
+
+impl Gender {
+    /// Licences encode the holder's gender as "01" for male and "02" for female.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "01" => Gender::Male,
+            "02" => Gender::Female,
+            _ => Gender::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Male => "male",
+            Gender::Female => "female",
+            Gender::Unknown => "unknown",
+        }
+    }
+
+    /// The ISO/IEC 5218 code: 0 not known, 1 male, 2 female.
+    pub fn iso_5218(&self) -> u8 {
+        match self {
+            Gender::Unknown => 0,
+            Gender::Male => 1,
+            Gender::Female => 2,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum GenderFormat {
+    /// "male", "female" or "unknown".
+    #[default]
+    Text,
+    /// ISO/IEC 5218 numeric codes.
+    Iso5218,
+}
+
+/// The holder's gender as it appears in the output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(untagged)]
+pub enum GenderValue {
+    Text(String),
+    Iso5218(u8),
+}
+
+impl GenderValue {
+    pub fn new(gender: Gender, format: GenderFormat) -> Self {
+        match format {
+            GenderFormat::Text => GenderValue::Text(gender.as_str().to_string()),
+            GenderFormat::Iso5218 => GenderValue::Iso5218(gender.iso_5218()),
+        }
+    }
+
+    pub fn gender(&self) -> Gender {
+        match self {
+            GenderValue::Text(text) if text == "male" => Gender::Male,
+            GenderValue::Text(text) if text == "female" => Gender::Female,
+            GenderValue::Iso5218(1) => Gender::Male,
+            GenderValue::Iso5218(2) => Gender::Female,
+            _ => Gender::Unknown,
+        }
+    }
+}
+
+impl Default for GenderValue {
+    fn default() -> Self {
+        GenderValue::new(Gender::Unknown, GenderFormat::Text)
+    }
+}
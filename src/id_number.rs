@@ -0,0 +1,124 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::Error as _;
+use serde::ser::{SerializeStruct, Serializer};
+use crate::date::LicenseDate;
+use crate::gender::Gender;
+
+const ID_NUMBER_LENGTH: usize = 13;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct IdNumberValidation {
+    pub check_digit_valid: bool,
+    pub birthdate_matches: bool,
+    pub gender_matches: bool,
+}
+
+/// The kind of identity document behind `id_number`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum IdDocumentType {
+    RsaId,
+    ForeignId,
+    Unknown(u8),
+}
+
+impl IdDocumentType {
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            1 => IdDocumentType::RsaId,
+            2 => IdDocumentType::ForeignId,
+            other => IdDocumentType::Unknown(other),
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            IdDocumentType::RsaId => 1,
+            IdDocumentType::ForeignId => 2,
+            IdDocumentType::Unknown(code) => *code,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            IdDocumentType::RsaId => "RSA ID document",
+            IdDocumentType::ForeignId => "foreign ID document or passport",
+            IdDocumentType::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl Default for IdDocumentType {
+    fn default() -> Self {
+        IdDocumentType::Unknown(0)
+    }
+}
+
+// Serialized as `{ code, description }`, with the code zero-padded like `id_number_type`.
+impl Serialize for IdDocumentType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("IdDocumentType", 2)?;
+        state.serialize_field("code", &format!("{:02}", self.code()))?;
+        state.serialize_field("description", self.description())?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct IdDocumentTypeCode {
+    code: String,
+}
+
+// Reads back the serialized `{ code, description }`. The description is derived from the code, so it is ignored.
+impl<'de> Deserialize<'de> for IdDocumentType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let IdDocumentTypeCode { code } = IdDocumentTypeCode::deserialize(deserializer)?;
+        let code = code.trim().parse().map_err(|_| D::Error::custom(format!("invalid ID document type code {}", code)))?;
+        Ok(IdDocumentType::from_code(code))
+    }
+}
+
+/// Validates a South African ID number (YYMMDD SSSS C A Z) against the license's birthdate and gender.
+/// Returns None when the number is not a 13 digit SA ID, e.g. for foreign identity documents.
+pub fn validate(id_number: &str, birthdate: Option<LicenseDate>, gender: Gender) -> Option<IdNumberValidation> {
+    let digits: Vec<u8> = id_number.trim().bytes().map(|b| b.wrapping_sub(b'0')).collect();
+    if digits.len() != ID_NUMBER_LENGTH || digits.iter().any(|&d| d > 9) {
+        return None;
+    }
+
+    let birthdate_matches = birthdate.is_some_and(|date| {
+        (date.year % 100) as u8 == digits[0] * 10 + digits[1]
+            && date.month == digits[2] * 10 + digits[3]
+            && date.day == digits[4] * 10 + digits[5]
+    });
+
+    // Sequence numbers 0000-4999 are assigned to females and 5000-9999 to males.
+    let id_gender = if digits[6] >= 5 { Gender::Male } else { Gender::Female };
+
+    Some(IdNumberValidation {
+        check_digit_valid: luhn_valid(&digits),
+        birthdate_matches,
+        gender_matches: gender == id_gender,
+    })
+}
+
+fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            let d = d as u32;
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
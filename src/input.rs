@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, Engine, GeneralPurpose, GeneralPurposeConfig};
+use crate::error::DecodeError;
+use crate::license;
+
+const DRIVERS_LICENSE_LENGTH: usize = 720;
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Decodes standard base64, ignoring whitespace and missing padding.
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, DecodeError> {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    Ok(BASE64.decode(compact)?)
+}
+
+/// Decodes a hex dump such as "01e10245...", "01 e1 02 45" or "0x01, 0xe1".
+pub fn decode_hex(input: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut digits = Vec::with_capacity(input.len());
+    for token in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let token = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+        for c in token.chars() {
+            digits.push(c.to_digit(16).ok_or(DecodeError::InvalidHex)? as u8);
+        }
+    }
+
+    if digits.len() % 2 != 0 {
+        return Err(DecodeError::InvalidHex);
+    }
+
+    Ok(digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+}
+
+/// Unwraps a payload copied out of a log or scanner SDK so format detection sees the payload itself.
+///
+/// Strips a trailing line ending, byte order marks and AIM symbology identifiers (e.g. "]L2"), plus the surrounding
+/// whitespace of text payloads, unpacks `data:` URIs and decodes base64, including a base64 token embedded in a log
+/// line. A raw 720-byte payload is returned as is, and anything unrecognised is returned with only the stripping
+/// applied.
+pub fn normalize(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if bytes.len() == DRIVERS_LICENSE_LENGTH {
+        return Cow::Borrowed(bytes);
+    }
+
+    let payload = strip_wrapping(bytes);
+    if let Some(body) = payload.strip_prefix(b"data:") {
+        let Some(comma) = body.iter().position(|&b| b == b',') else {
+            return Cow::Borrowed(payload);
+        };
+        let (media_type, data) = (&body[..comma], strip_wrapping(&body[comma + 1..]));
+        if !media_type.ends_with(b";base64") {
+            return Cow::Borrowed(data);
+        }
+        return match std::str::from_utf8(data).map(decode_base64) {
+            Ok(Ok(decoded)) => Cow::Owned(decoded),
+            _ => Cow::Borrowed(data),
+        };
+    }
+
+    if license::detect_raw_format(payload).is_some() {
+        return Cow::Borrowed(payload);
+    }
+
+    let Ok(text) = std::str::from_utf8(payload) else {
+        return Cow::Borrowed(payload);
+    };
+    // The whole text first, as base64 may be wrapped across lines; then each token, for a payload inside a log line.
+    let tokens = text.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | ':' | ';' | '(' | ')' | '[' | ']'));
+    std::iter::once(text)
+        .chain(tokens)
+        .filter_map(|candidate| decode_base64(candidate).ok())
+        .find(|decoded| license::detect_raw_format(decoded).is_some())
+        .map_or(Cow::Borrowed(payload), Cow::Owned)
+}
+
+// Binary payloads lose at most one line ending and the BOM and AIM prefixes, as any trailing ciphertext byte may
+// happen to be a whitespace value. Text (base64, hex, `data:` URIs and discs) is trimmed as well.
+fn strip_wrapping(bytes: &[u8]) -> &[u8] {
+    let mut bytes = strip_line_ending(bytes);
+    loop {
+        let is_text = std::str::from_utf8(bytes).is_ok();
+        let trimmed = if is_text { bytes.trim_ascii() } else { bytes };
+        let trimmed = trimmed.strip_prefix(UTF8_BOM).unwrap_or(trimmed);
+        let trimmed = match trimmed {
+            [b']', code, modifier, rest @ ..] if code.is_ascii_alphabetic() && modifier.is_ascii_alphanumeric() => rest,
+            _ => trimmed,
+        };
+        if !is_text || trimmed.len() == bytes.len() {
+            return trimmed;
+        }
+        bytes = trimmed;
+    }
+}
+
+// The CR, LF or CRLF a scanner appends after the payload.
+fn strip_line_ending(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.strip_suffix(b"\r").unwrap_or(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A version 1 payload whose last ciphertext byte happens to be a space.
+    fn payload() -> Vec<u8> {
+        let mut payload = vec![0x01, 0xe1, 0x02, 0x45, 0x00, 0x00];
+        payload.extend((0..DRIVERS_LICENSE_LENGTH - 7).map(|i| (i * 37 % 256) as u8 | 0x80));
+        payload.push(b' ');
+        payload
+    }
+
+    fn with(prefix: &[u8], payload: &[u8], suffix: &[u8]) -> Vec<u8> {
+        [prefix, payload, suffix].concat()
+    }
+
+    #[test]
+    fn raw_payloads_keep_trailing_whitespace_bytes() {
+        let payload = payload();
+        assert_eq!(&*normalize(&payload), payload);
+        assert_eq!(&*normalize(&with(b"", &payload, b"\n")), payload);
+        assert_eq!(&*normalize(&with(b"", &payload, b"\r\n")), payload);
+        assert_eq!(&*normalize(&with(b"", &payload[..700], b"\n")), &payload[..700]);
+    }
+
+    #[test]
+    fn raw_payloads_lose_bom_and_aim_prefixes() {
+        let payload = payload();
+        assert_eq!(&*normalize(&with(b"]L2", &payload, b"\r")), payload);
+        assert_eq!(&*normalize(&with(UTF8_BOM, &payload, b"")), payload);
+    }
+
+    #[test]
+    fn text_payloads_are_trimmed_and_decoded() {
+        let payload = payload();
+        let encoded = BASE64.encode(&payload);
+        assert_eq!(&*normalize(format!("  {}\n\n", encoded).as_bytes()), payload);
+        assert_eq!(&*normalize(format!("]L2 data:application/octet-stream;base64,{}\n", encoded).as_bytes()), payload);
+        assert_eq!(&*normalize(format!("INFO scan payload=\"{}\" ok", encoded).as_bytes()), payload);
+        assert_eq!(&*normalize(b"  %MVL1CC14%0154%  \r\n"), b"%MVL1CC14%0154%");
+    }
+
+    #[test]
+    fn unrecognised_input_is_only_stripped() {
+        assert_eq!(&*normalize(b"\xef\xbb\xbf hello \n"), b"hello");
+        assert_eq!(&*normalize(b"data:text/plain,hello"), b"hello");
+    }
+}
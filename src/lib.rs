@@ -1,20 +1,167 @@
-mod drivers_license;
-mod vehicle_license;
+pub mod aamva;
+pub mod accumulator;
+pub mod country;
+pub mod csv;
+pub mod date;
+pub mod drivers_license;
+pub mod error;
+pub mod fields;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod gender;
+pub mod id_number;
+pub mod input;
+pub mod license;
+pub mod license_code;
+pub mod mdl;
+mod license_plate;
+#[cfg(feature = "uniffi")]
+mod mobile;
+// napi-derive only registers the exports outside of test builds.
+#[cfg(feature = "napi")]
+#[cfg_attr(test, allow(dead_code))]
+mod node;
+pub mod output;
+pub mod prdp;
+#[cfg(feature = "python")]
+mod python;
+pub mod reader;
+pub mod restriction;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod segments;
+pub mod smart_id;
+pub mod text;
+pub mod validation;
+pub mod vcard;
+#[cfg(feature = "vc")]
+pub mod vc;
+pub mod vehicle_license;
+pub mod warning;
+pub mod wedge;
+#[cfg(feature = "xml")]
+pub mod xml;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-use wasm_bindgen::prelude::*;
-use drivers_license::{parse_bytes as parse_drivers_bytes, DriversLicenseData};
-use vehicle_license::{parse_bytes as parse_vehicle_bytes, VehicleLicenseData};
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
-#[wasm_bindgen]
-pub fn parse_drivers_license(bytes: &[u8]) -> Result<JsValue, JsValue> {
-    parse_drivers_bytes(bytes.to_vec())
-        .map(|data: DriversLicenseData| serde_wasm_bindgen::to_value(&data).expect("Failed to serialize to JsValue"))
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+// The wasm-bindgen glue needs a JS host, which WASI runtimes don't provide.
+#[cfg(all(feature = "wasm", target_os = "wasi"))]
+compile_error!("the wasm feature targets JS hosts; build for WASI with --no-default-features");
+
+pub use accumulator::{AccumulatedScan, ScanAccumulator};
+pub use date::LicenseDate;
+pub use drivers_license::{AgeVerification, DriversLicenseData};
+#[cfg(feature = "test-keys")]
+pub use drivers_license::{SigningKeys, test_keys};
+pub use error::DecodeError;
+pub use license::{License, LicenseFormat};
+pub use segments::{MacroSegment, SegmentAssembler};
+pub use smart_id::SmartIdData;
+pub use validation::ValidationReport;
+pub use vehicle_license::VehicleLicenseData;
+pub use warning::Warning;
+
+pub fn parse_license(bytes: &[u8]) -> Result<License, DecodeError> {
+    license::parse_bytes(bytes)
+}
+
+/// Parses any registered format, reporting its format id (e.g. "sa_drivers_v2") alongside the data or error.
+pub fn parse_any(bytes: &[u8]) -> license::AnyDocument {
+    license::parse_any(bytes, license::ParseOptions::default())
+}
+
+/// Parses any registered format into `{ schemaVersion, format, data }`, see `license::SCHEMA_VERSION`.
+pub fn parse_versioned(bytes: &[u8]) -> Result<license::VersionedDocument, DecodeError> {
+    license::parse_versioned(bytes, license::ParseOptions::default())
+}
+
+/// Ranked guesses at the payload's format with a confidence and reason each, e.g. for routing or for telling a user
+/// "this looks like a vehicle disc, not a driving licence".
+pub fn sniff(bytes: &[u8]) -> Vec<license::FormatCandidate> {
+    license::sniff(bytes)
+}
+
+pub fn detect_format(bytes: &[u8]) -> Option<LicenseFormat> {
+    license::detect_format(bytes)
+}
+
+/// Unwraps base64, `data:` URIs, AIM identifiers, byte order marks and whitespace around a copied payload.
+/// `parse_license` and `detect_format` already do this.
+pub fn normalize_input(bytes: &[u8]) -> Vec<u8> {
+    input::normalize(bytes).into_owned()
+}
+
+pub fn get_license_version(bytes: &[u8]) -> Option<u8> {
+    drivers_license::license_version(bytes)
+}
+
+pub fn parse_drivers_license(bytes: &[u8]) -> Result<DriversLicenseData, DecodeError> {
+    drivers_license::parse_bytes(bytes)
+}
+
+/// Parses each payload independently, so one bad scan doesn't fail the rest of the batch.
+pub fn parse_drivers_licenses<'a>(payloads: impl IntoIterator<Item = &'a [u8]>) -> Vec<Result<DriversLicenseData, DecodeError>> {
+    payloads.into_iter().map(parse_drivers_license).collect()
+}
+
+/// Builds a 720-byte payload from `data` signed with `keys`, for round-trip tests and synthetic QA cards.
+#[cfg(feature = "test-keys")]
+pub fn encode_drivers_license(data: &DriversLicenseData, keys: &SigningKeys) -> Result<Vec<u8>, DecodeError> {
+    drivers_license::encode(data, keys)
+}
+
+pub fn decrypt_drivers_license(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    drivers_license::decrypt_bytes(bytes)
+}
+
+/// Age gating without handing the birthdate or ID number to the caller.
+pub fn is_older_than(bytes: &[u8], years: u16, today: LicenseDate) -> Result<AgeVerification, DecodeError> {
+    drivers_license::verify_age(bytes, years, today)
+}
+
+/// Cross-field checks answering "should a person double-check this card?".
+pub fn validate_drivers_license(bytes: &[u8]) -> Result<ValidationReport, DecodeError> {
+    drivers_license::parse_bytes(bytes).map(|data| validation::validate(&data))
+}
+
+pub fn parse_vehicle_license(bytes: &[u8]) -> Result<VehicleLicenseData, DecodeError> {
+    vehicle_license::parse_bytes(bytes)
+}
+
+pub fn parse_vehicle_license_string(input: &str) -> Result<VehicleLicenseData, DecodeError> {
+    vehicle_license::parse_string(input)
+}
+
+/// The `%`-delimited disc string for `data`, which parses back to the same fields.
+pub fn encode_vehicle_license(data: &VehicleLicenseData) -> String {
+    vehicle_license::encode(data)
+}
+
+/// Best-effort recovery of the scanned bytes from text typed by a keyboard-wedge scanner.
+pub fn repair_wedge_text(text: &str, layout: wedge::KeyboardLayout) -> wedge::WedgeRepair {
+    wedge::repair(text, layout)
+}
+
+/// Parses the PDF417 on the back of a South African smart ID card.
+pub fn parse_smart_id_card(bytes: &[u8]) -> Result<SmartIdData, DecodeError> {
+    smart_id::parse_bytes(bytes)
+}
+
+pub fn parse_drivers_license_base64(input: &str) -> Result<DriversLicenseData, DecodeError> {
+    drivers_license::parse_bytes(&input::decode_base64(input)?)
+}
+
+pub fn parse_vehicle_license_base64(input: &str) -> Result<VehicleLicenseData, DecodeError> {
+    vehicle_license::parse_bytes(&input::decode_base64(input)?)
+}
+
+pub fn parse_drivers_license_hex(input: &str) -> Result<DriversLicenseData, DecodeError> {
+    drivers_license::parse_bytes(&input::decode_hex(input)?)
 }
 
-#[wasm_bindgen]
-pub fn parse_vehicle_license(bytes: &[u8]) -> Result<JsValue, JsValue> {
-    parse_vehicle_bytes(bytes.to_vec())
-        .map(|data: VehicleLicenseData| serde_wasm_bindgen::to_value(&data).expect("Failed to serialize to JsValue"))
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+pub fn parse_vehicle_license_hex(input: &str) -> Result<VehicleLicenseData, DecodeError> {
+    vehicle_license::parse_bytes(&input::decode_hex(input)?)
 }
@@ -0,0 +1,395 @@
+use serde::{Deserialize, Serialize, Serializer};
+use crate::date::LicenseDate;
+use crate::error::DecodeError;
+use crate::input;
+use crate::drivers_license::{self, DriversLicenseData, ParseOptions as DriversParseOptions};
+use crate::smart_id::{self, SmartIdData, ParseOptions as SmartIdParseOptions};
+use crate::vehicle_license::{self, VehicleLicenseData, ParseOptions as VehicleParseOptions};
+
+const DRIVERS_LICENSE_LENGTH: usize = 720;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseFormat {
+    Drivers,
+    Vehicle,
+    #[serde(rename = "smart_id")]
+    SmartId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum License {
+    Drivers(DriversLicenseData),
+    Vehicle(VehicleLicenseData),
+    #[serde(rename = "smart_id")]
+    SmartId(SmartIdData),
+}
+
+impl License {
+    pub fn format(&self) -> LicenseFormat {
+        match self {
+            License::Drivers(_) => LicenseFormat::Drivers,
+            License::Vehicle(_) => LicenseFormat::Vehicle,
+            License::SmartId(_) => LicenseFormat::SmartId,
+        }
+    }
+
+    /// Whether the licence or disc was valid on `date`, e.g. the date of an incident.
+    pub fn is_valid_on(&self, date: LicenseDate) -> Option<bool> {
+        match self {
+            License::Drivers(data) => data.is_valid_on(date),
+            License::Vehicle(data) => data.is_valid_on(date),
+            License::SmartId(data) => data.is_valid_on(date),
+        }
+    }
+}
+
+/// Options for each format, applied to whichever one the input turns out to be.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(default, rename = "LicenseParseOptions")]
+pub struct ParseOptions {
+    pub drivers: DriversParseOptions,
+    pub vehicle: VehicleParseOptions,
+    #[serde(alias = "smartId")]
+    pub smart_id: SmartIdParseOptions,
+}
+
+/// A document format the unified entry points can recognise and parse. Formats are tried in `PARSERS` order.
+pub trait LicenseParser: Sync {
+    /// A stable identifier for the format, e.g. "sa_drivers".
+    fn format_id(&self) -> &'static str;
+    fn format(&self) -> LicenseFormat;
+    /// Whether the payload looks like this format, judged from its length, header or delimiters without decrypting.
+    fn sniff(&self, bytes: &[u8]) -> bool;
+    /// Like `sniff`, but also accepts payloads that only parse with `options`, e.g. a tolerated length.
+    fn sniff_with_options(&self, bytes: &[u8], _options: &ParseOptions) -> bool {
+        self.sniff(bytes)
+    }
+    fn parse(&self, bytes: &[u8], options: &ParseOptions) -> Result<License, DecodeError>;
+
+    /// The format id of this payload in particular, e.g. with its version appended. Defaults to `format_id`.
+    fn payload_format_id(&self, _bytes: &[u8]) -> String {
+        self.format_id().to_string()
+    }
+
+    /// How likely the payload is this format, from 0 to 1, and why. None when it clearly isn't.
+    /// Unlike `sniff`, near misses such as a truncated payload are reported with a low confidence.
+    fn assess(&self, bytes: &[u8]) -> Option<(f32, String)> {
+        self.sniff(bytes).then(|| (0.5, format!("Recognised as {}", self.format_id())))
+    }
+}
+
+/// A format a payload might be, as ranked by `sniff`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct FormatCandidate {
+    /// The format id, as in `parse_any`.
+    pub format: String,
+    pub confidence: f32,
+    pub reason: String,
+}
+
+struct DriversLicenseParser;
+struct VehicleLicenseParser;
+struct SmartIdParser;
+
+impl LicenseParser for DriversLicenseParser {
+    fn format_id(&self) -> &'static str {
+        "sa_drivers"
+    }
+
+    fn format(&self) -> LicenseFormat {
+        LicenseFormat::Drivers
+    }
+
+    // An encrypted payload with an unfamiliar header is still a driver's license, so its parser can report the
+    // version. Ciphertext is never valid UTF-8, which tells it apart from a text payload of the same length.
+    fn sniff(&self, bytes: &[u8]) -> bool {
+        bytes.len() == DRIVERS_LICENSE_LENGTH
+            && (drivers_license::has_known_header(bytes) || std::str::from_utf8(bytes).is_err())
+    }
+
+    fn sniff_with_options(&self, bytes: &[u8], options: &ParseOptions) -> bool {
+        self.sniff(bytes) || drivers_license::has_tolerated_header(bytes, &options.drivers)
+    }
+
+    fn parse(&self, bytes: &[u8], options: &ParseOptions) -> Result<License, DecodeError> {
+        Ok(License::Drivers(drivers_license::parse_bytes_with_options(bytes, options.drivers.clone())?))
+    }
+
+    fn assess(&self, bytes: &[u8]) -> Option<(f32, String)> {
+        let version = drivers_license::license_version(bytes);
+        match (bytes.len(), version) {
+            (DRIVERS_LICENSE_LENGTH, Some(version)) => Some((0.95, format!("720 bytes with the version {} header", version))),
+            (DRIVERS_LICENSE_LENGTH, None) if std::str::from_utf8(bytes).is_err() => {
+                Some((0.6, "720 bytes of binary data, but the header isn't a known version".to_string()))
+            }
+            (length, Some(version)) => {
+                Some((0.3, format!("Version {} header, but {} bytes instead of 720, e.g. a partial scan", version, length)))
+            }
+            _ => None,
+        }
+    }
+
+    // e.g. "sa_drivers_v2", or "sa_drivers" when the header isn't a known version.
+    fn payload_format_id(&self, bytes: &[u8]) -> String {
+        match drivers_license::license_version(bytes) {
+            Some(version) => format!("{}_v{}", self.format_id(), version),
+            None => self.format_id().to_string(),
+        }
+    }
+}
+
+impl LicenseParser for VehicleLicenseParser {
+    fn format_id(&self) -> &'static str {
+        "sa_vehicle"
+    }
+
+    fn format(&self) -> LicenseFormat {
+        LicenseFormat::Vehicle
+    }
+
+    // Vehicle discs are plain text with '%' separating the fields. A disc with a corrupted byte is recognised from its
+    // structure, so the `lossy_utf8` option can still read it.
+    fn sniff(&self, bytes: &[u8]) -> bool {
+        bytes.contains(&b'%') && (std::str::from_utf8(bytes).is_ok() || vehicle_license::looks_like_disc(bytes))
+    }
+
+    fn parse(&self, bytes: &[u8], options: &ParseOptions) -> Result<License, DecodeError> {
+        Ok(License::Vehicle(vehicle_license::parse_bytes_with_options(bytes, options.vehicle)?))
+    }
+
+    fn assess(&self, bytes: &[u8]) -> Option<(f32, String)> {
+        if !bytes.contains(&b'%') {
+            return None;
+        }
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return Some((0.2, "'%'-separated fields, but not valid UTF-8; try the lossy_utf8 option".to_string()));
+        };
+        let parts: Vec<&str> = text.split('%').collect();
+        if vehicle_license::matches_layout(&parts) {
+            Some((0.9, format!("{} '%'-separated fields, as on a licence disc", parts.len())))
+        } else {
+            Some((0.4, format!("Only {} '%'-separated fields, too few for a licence disc", parts.len())))
+        }
+    }
+}
+
+impl LicenseParser for SmartIdParser {
+    fn format_id(&self) -> &'static str {
+        "sa_smart_id"
+    }
+
+    fn format(&self) -> LicenseFormat {
+        LicenseFormat::SmartId
+    }
+
+    // Smart ID cards are plain text with '|' separating the fields.
+    fn sniff(&self, bytes: &[u8]) -> bool {
+        smart_id::is_smart_id(bytes)
+    }
+
+    fn parse(&self, bytes: &[u8], options: &ParseOptions) -> Result<License, DecodeError> {
+        Ok(License::SmartId(smart_id::parse_bytes_with_options(bytes, options.smart_id)?))
+    }
+
+    fn assess(&self, bytes: &[u8]) -> Option<(f32, String)> {
+        if smart_id::is_smart_id(bytes) {
+            return Some((0.9, "'|'-separated fields with a 13 digit ID number".to_string()));
+        }
+        let text = std::str::from_utf8(bytes).ok().filter(|text| text.contains('|'))?;
+        Some((0.2, format!("{} '|'-separated fields, but no 13 digit ID number where a smart ID card has one", text.split('|').count())))
+    }
+}
+
+/// The `format` of a payload that no registered parser recognises.
+pub const UNRECOGNIZED_FORMAT_ID: &str = "unrecognized";
+
+/// What `parse_any` made of a payload: its format id and the parsed data, or why it couldn't be parsed.
+#[derive(Debug)]
+pub struct AnyDocument {
+    /// The payload's format id, e.g. "sa_drivers_v2", or `UNRECOGNIZED_FORMAT_ID`.
+    pub format: String,
+    pub result: Result<License, DecodeError>,
+}
+
+/// The version of the output shape. Bumped whenever a field is renamed, removed or changes type; new fields don't
+/// bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Parsed data with the output schema version and format id, e.g. `{ schemaVersion: 1, format: "sa_vehicle", data }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct VersionedDocument {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    /// The payload's format id, e.g. "sa_drivers_v2".
+    pub format: String,
+    /// The data struct on its own, without the `{ type, data }` wrapping of `License`.
+    #[serde(serialize_with = "serialize_license_data")]
+    #[cfg_attr(feature = "wasm", tsify(type = "DriversLicenseData | VehicleLicenseData | SmartIdData"))]
+    pub data: License,
+}
+
+fn serialize_license_data<S: Serializer>(license: &License, serializer: S) -> Result<S::Ok, S::Error> {
+    match license {
+        License::Drivers(data) => data.serialize(serializer),
+        License::Vehicle(data) => data.serialize(serializer),
+        License::SmartId(data) => data.serialize(serializer),
+    }
+}
+
+/// Every format `parse_license` and `detect_format` recognise. A new format is supported by adding its parser here.
+pub static PARSERS: [&dyn LicenseParser; 3] = [&DriversLicenseParser, &VehicleLicenseParser, &SmartIdParser];
+
+/// Identifies the payload type from its length, header and delimiters, without decrypting or parsing it.
+/// The payload is unwrapped with `input::normalize` first.
+pub fn detect_format(bytes: &[u8]) -> Option<LicenseFormat> {
+    detect_raw_format(&input::normalize(bytes))
+}
+
+pub(crate) fn detect_raw_format(bytes: &[u8]) -> Option<LicenseFormat> {
+    sniff_parser(bytes, &ParseOptions::default()).map(|parser| parser.format())
+}
+
+fn sniff_parser(bytes: &[u8], options: &ParseOptions) -> Option<&'static dyn LicenseParser> {
+    PARSERS.iter().copied().find(|parser| parser.sniff_with_options(bytes, options))
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<License, DecodeError> {
+    parse_bytes_with_options(bytes, ParseOptions::default())
+}
+
+pub fn parse_bytes_with_options(bytes: &[u8], options: ParseOptions) -> Result<License, DecodeError> {
+    let bytes = &*input::normalize(bytes);
+    let parser = sniff_parser(bytes, &options).ok_or(DecodeError::UnrecognizedFormat)?;
+    parser.parse(bytes, &options)
+}
+
+/// Like `parse_bytes_with_options`, but also reports the payload's format id, including when it is unrecognised.
+pub fn parse_any(bytes: &[u8], options: ParseOptions) -> AnyDocument {
+    let bytes = &*input::normalize(bytes);
+    match sniff_parser(bytes, &options) {
+        Some(parser) => AnyDocument { format: parser.payload_format_id(bytes), result: parser.parse(bytes, &options) },
+        None => AnyDocument { format: UNRECOGNIZED_FORMAT_ID.to_string(), result: Err(DecodeError::UnrecognizedFormat) },
+    }
+}
+
+/// Reads a format name: "drivers", "vehicle" or "smart_id", or a format id such as "sa_drivers" or "sa_drivers_v2".
+pub fn format_from_name(name: &str) -> Option<LicenseFormat> {
+    match name {
+        "drivers" => Some(LicenseFormat::Drivers),
+        "vehicle" => Some(LicenseFormat::Vehicle),
+        "smart_id" => Some(LicenseFormat::SmartId),
+        _ => PARSERS
+            .iter()
+            .find(|parser| {
+                let versioned = name.strip_prefix(parser.format_id()).and_then(|rest| rest.strip_prefix("_v"));
+                name == parser.format_id() || versioned.is_some_and(|version| version.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .map(|parser| parser.format()),
+    }
+}
+
+/// Like `parse_any`, but wraps the data in a `VersionedDocument` so consumers can tell when the output shape changes.
+pub fn parse_versioned(bytes: &[u8], options: ParseOptions) -> Result<VersionedDocument, DecodeError> {
+    let document = parse_any(bytes, options);
+    document.result.map(|data| VersionedDocument { schema_version: SCHEMA_VERSION, format: document.format, data })
+}
+
+/// Every format the payload might be, most likely first, judged without decrypting or parsing it.
+/// Empty when no registered format comes close.
+pub fn sniff(bytes: &[u8]) -> Vec<FormatCandidate> {
+    let bytes = &*input::normalize(bytes);
+    let mut candidates: Vec<FormatCandidate> = PARSERS
+        .iter()
+        .filter_map(|parser| {
+            let (confidence, reason) = parser.assess(bytes)?;
+            Some(FormatCandidate { format: parser.payload_format_id(bytes), confidence, reason })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISC: &str = "%MVL1CC14%0154%4025T0JB%1%40250031T0JB%CY123456%ABC123GP%Hatch back / Luikrug%VOLKSWAGEN%POLO%White / Wit%AAVZZZ6RZCU012345%CJZ123456%2025-07-31%";
+
+    #[test]
+    fn options_accept_camel_case_keys() {
+        let options: ParseOptions = serde_json::from_str(
+            r#"{ "drivers": { "tolerantLength": true, "camelCase": true }, "vehicle": { "lossyUtf8": true, "dateFormat": "iso" }, "smartId": { "camelCase": true } }"#,
+        )
+        .expect("camelCase options deserialize");
+        assert!(options.drivers.tolerant_length && options.drivers.camel_case);
+        assert!(options.vehicle.lossy_utf8);
+        assert_eq!(options.vehicle.date_format, crate::date::DateFormat::Iso);
+        assert!(options.smart_id.camel_case);
+
+        let options: ParseOptions = serde_json::from_str(r#"{ "drivers": { "tolerant_length": true }, "smart_id": { "camel_case": true } }"#)
+            .expect("snake_case options deserialize");
+        assert!(options.drivers.tolerant_length && options.smart_id.camel_case);
+    }
+
+    #[test]
+    fn discs_with_invalid_utf8_reach_the_lossy_option() {
+        let mut bytes = DISC.as_bytes().to_vec();
+        bytes[DISC.find("POLO").expect("the disc has a model")] = 0xff;
+        assert_eq!(detect_format(&bytes), Some(LicenseFormat::Vehicle));
+        assert!(matches!(parse_bytes(&bytes), Err(DecodeError::InvalidUtf8(_))));
+
+        let options = ParseOptions { vehicle: VehicleParseOptions { lossy_utf8: true, ..Default::default() }, ..Default::default() };
+        let Ok(License::Vehicle(disc)) = parse_bytes_with_options(&bytes, options) else {
+            panic!("the disc parses lossily");
+        };
+        assert_eq!(disc.model, "\u{fffd}OLO");
+        assert_eq!(disc.warnings.iter().map(|warning| warning.field.as_deref()).collect::<Vec<_>>(), [Some("model")]);
+    }
+
+    // Five bytes either side of a version 1 payload, as a scanner that adds framing might return it.
+    fn framed_payload(payload: &[u8]) -> Vec<u8> {
+        [&[0xaa; 5][..], payload, &[0xbb; 5]].concat()
+    }
+
+    #[test]
+    fn tolerant_length_applies_through_parse_license() {
+        let mut payload = vec![0x01, 0xe1, 0x02, 0x45, 0x00, 0x00];
+        payload.extend((0..714).map(|i| (i * 37 % 256) as u8 | 0x80));
+        let framed = framed_payload(&payload);
+        assert!(matches!(parse_bytes(&framed), Err(DecodeError::UnrecognizedFormat)));
+
+        let drivers = DriversParseOptions { tolerant_length: true, lenient: true, ..Default::default() };
+        let document = parse_any(&framed, ParseOptions { drivers, ..Default::default() });
+        assert!(!matches!(document.result, Err(DecodeError::UnrecognizedFormat | DecodeError::InsufficientBytes)));
+        assert_eq!(document.format, "sa_drivers");
+    }
+
+    #[cfg(feature = "test-keys")]
+    #[test]
+    fn tolerant_length_parses_framed_payloads_through_parse_license() {
+        let keys = crate::drivers_license::test_keys();
+        let data = DriversLicenseData {
+            surname: "SMITH".to_string(),
+            id_number: "8503145800089".to_string(),
+            license_issue_number: "01".to_string(),
+            ..Default::default()
+        };
+        let framed = framed_payload(&drivers_license::encode(&data, &keys).expect("the licence encodes"));
+
+        let drivers = DriversParseOptions { tolerant_length: true, keys: vec![keys.version_keys()], ..Default::default() };
+        let Ok(License::Drivers(license)) = parse_bytes_with_options(&framed, ParseOptions { drivers, ..Default::default() }) else {
+            panic!("the framed payload parses");
+        };
+        assert_eq!(license.surname, "SMITH");
+        assert_eq!(license.warnings.iter().filter(|warning| warning.code == "IGNORED_BYTES").count(), 2);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::drivers_license::DriversLicenseData;
+
+// Each code and the lower codes it authorises, per the National Road Traffic Act.
+const IMPLIED_CODES: [(&str, &[&str]); 8] = [
+    ("A1", &[]),
+    ("A", &["A1"]),
+    ("B", &[]),
+    ("EB", &["B"]),
+    ("C1", &["B"]),
+    ("C", &["C1", "B"]),
+    ("EC1", &["C1", "EB", "B"]),
+    ("EC", &["EC1", "C", "C1", "EB", "B"]),
+];
+
+// Code A1 can be held from 16. Every other code requires the holder to be 18.
+const A1_MINIMUM_AGE: u16 = 16;
+const MINIMUM_AGE: u16 = 18;
+
+/// The youngest a holder may be when a code is issued. None for unknown codes.
+pub fn minimum_age(code: &str) -> Option<u16> {
+    let code = code.trim().to_ascii_uppercase();
+    match code.as_str() {
+        "A1" => Some(A1_MINIMUM_AGE),
+        _ if IMPLIED_CODES.iter().any(|(known, _)| *known == code) => Some(MINIMUM_AGE),
+        _ => None,
+    }
+}
+
+/// The lower codes a held code authorises, excluding the code itself. Empty for unknown codes.
+pub fn implied_codes(code: &str) -> &'static [&'static str] {
+    let code = code.trim().to_ascii_uppercase();
+    IMPLIED_CODES.iter().find(|(held, _)| *held == code).map(|(_, implied)| *implied).unwrap_or_default()
+}
+
+/// True when holding `held` authorises driving vehicles of code `wanted`.
+pub fn covers(held: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim().to_ascii_uppercase();
+    held.trim().eq_ignore_ascii_case(&wanted) || implied_codes(held).contains(&wanted.as_str())
+}
+
+/// True when any of the licence's codes authorises driving vehicles of code `wanted`.
+pub fn allows_driving(license: &DriversLicenseData, wanted: &str) -> bool {
+    license.vehicle_codes.iter().any(|held| covers(held, wanted))
+}
@@ -0,0 +1,188 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlateClassification {
+    pub valid: bool,
+    pub province: Option<&'static str>,
+    pub registration_authority: Option<&'static str>,
+}
+
+const WESTERN_CAPE: &str = "Western Cape";
+const KWAZULU_NATAL: &str = "KwaZulu-Natal";
+
+// Prefix plates ("CA 123-456", "ND 12345") identify the issuing office.
+const PREFIX_AUTHORITIES: [(&str, &str); 10] = [
+    ("CAW", "George"),
+    ("CA", "Cape Town"),
+    ("CY", "Bellville"),
+    ("CF", "Kuils River"),
+    ("CL", "Stellenbosch"),
+    ("CJ", "Paarl"),
+    ("CK", "Malmesbury"),
+    ("ND", "Durban"),
+    ("NP", "Pietermaritzburg"),
+    ("NN", "Newcastle"),
+];
+
+// Suffix plates ("ABC 123 GP") only identify the province. Longer suffixes are matched first.
+const PROVINCE_SUFFIXES: [(&str, &str); 9] = [
+    ("GP", "Gauteng"),
+    ("EC", "Eastern Cape"),
+    ("FS", "Free State"),
+    ("MP", "Mpumalanga"),
+    ("NW", "North West"),
+    ("NC", "Northern Cape"),
+    ("ZN", KWAZULU_NATAL),
+    ("WP", WESTERN_CAPE),
+    ("L", "Limpopo"),
+];
+
+const SUFFIX_PLATE_SHAPES: [&str; 3] = ["LLLDDD", "LLDDLL", "LLLDD"];
+// Personalised plates carry 2 to 7 letters and digits before the province suffix, at least one of them a letter.
+const PERSONALISED_LENGTHS: std::ops::RangeInclusive<usize> = 2..=7;
+
+const INVALID: PlateClassification = PlateClassification { valid: false, province: None, registration_authority: None };
+
+pub fn classify(license_number: &str) -> PlateClassification {
+    let plate: String = license_number
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-'))
+        .collect::<String>()
+        .to_ascii_uppercase();
+
+    if plate.is_empty() || !plate.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return INVALID;
+    }
+
+    if let Some(classification) = classify_prefix_plate(&plate) {
+        return classification;
+    }
+
+    for (suffix, province) in PROVINCE_SUFFIXES {
+        let Some(body) = plate.strip_suffix(suffix).filter(|body| !body.is_empty()) else {
+            continue;
+        };
+
+        if SUFFIX_PLATE_SHAPES.contains(&shape(body).as_str()) {
+            return PlateClassification { valid: true, province: Some(province), registration_authority: None };
+        }
+
+        // Personalised plates are legitimate but don't follow a format we can classify.
+        if is_personalised(body) {
+            return PlateClassification { valid: true, province: None, registration_authority: None };
+        }
+    }
+
+    INVALID
+}
+
+fn classify_prefix_plate(plate: &str) -> Option<PlateClassification> {
+    let letters = plate.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+    let digits = &plate[letters..];
+
+    if !(1..=3).contains(&letters) || digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let province = match plate.as_bytes()[0] {
+        b'C' => WESTERN_CAPE,
+        b'N' => KWAZULU_NATAL,
+        _ => return None,
+    };
+
+    let prefix = &plate[..letters];
+    let registration_authority = PREFIX_AUTHORITIES
+        .iter()
+        .find(|(code, _)| *code == prefix)
+        .map(|(_, authority)| *authority);
+
+    Some(PlateClassification { valid: true, province: Some(province), registration_authority })
+}
+
+fn is_personalised(body: &str) -> bool {
+    PERSONALISED_LENGTHS.contains(&body.len()) && body.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn shape(value: &str) -> String {
+    value.chars().map(|c| if c.is_ascii_digit() { 'D' } else { 'L' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classified(plate: &str) -> (bool, Option<&'static str>, Option<&'static str>) {
+        let classification = classify(plate);
+        (classification.valid, classification.province, classification.registration_authority)
+    }
+
+    #[test]
+    fn prefix_plates_name_the_province_and_office() {
+        let cases = [
+            ("CAW 12345", WESTERN_CAPE, Some("George")),
+            ("CA 123-456", WESTERN_CAPE, Some("Cape Town")),
+            ("CY 12345", WESTERN_CAPE, Some("Bellville")),
+            ("CF 1234", WESTERN_CAPE, Some("Kuils River")),
+            ("CL 123-456", WESTERN_CAPE, Some("Stellenbosch")),
+            ("CJ 123", WESTERN_CAPE, Some("Paarl")),
+            ("CK 12345", WESTERN_CAPE, Some("Malmesbury")),
+            ("ND 12345", KWAZULU_NATAL, Some("Durban")),
+            ("NP 123-456", KWAZULU_NATAL, Some("Pietermaritzburg")),
+            ("NN 1234", KWAZULU_NATAL, Some("Newcastle")),
+            ("CEY 123", WESTERN_CAPE, None),
+            ("NU 12345", KWAZULU_NATAL, None),
+        ];
+        for (plate, province, authority) in cases {
+            assert_eq!(classified(plate), (true, Some(province), authority), "{}", plate);
+        }
+    }
+
+    #[test]
+    fn suffix_plates_name_the_province() {
+        let cases = [
+            ("BCD 123 GP", "Gauteng"),
+            ("BC 12 DF GP", "Gauteng"),
+            ("BCD 123 EC", "Eastern Cape"),
+            ("BCD 12 FS", "Free State"),
+            ("BC 12 DF MP", "Mpumalanga"),
+            ("BCD 123 NW", "North West"),
+            ("BCD 123 NC", "Northern Cape"),
+            ("BCD 123 ZN", KWAZULU_NATAL),
+            ("BCD 123 WP", WESTERN_CAPE),
+            ("BCD 123 L", "Limpopo"),
+        ];
+        for (plate, province) in cases {
+            assert_eq!(classified(plate), (true, Some(province), None), "{}", plate);
+        }
+    }
+
+    #[test]
+    fn personalised_plates_are_valid_but_unclassified() {
+        for plate in ["JOHN GP", "ZA 1 WP", "MY CAR ZN", "BOSS L", "A1 L", "SEVEN77 EC"] {
+            assert_eq!(classified(plate), (true, None, None), "{}", plate);
+        }
+    }
+
+    #[test]
+    fn malformed_plates_are_invalid() {
+        let cases = [
+            "",
+            "   ",
+            "L",
+            "GP",
+            "A L",
+            "7 GP",
+            "123 L",
+            "123456 GP",
+            "TOOLONGX L",
+            "ABCDEFGH GP",
+            "AB_C GP",
+            "BCD 123 XX",
+            "ZZ 12345",
+            "CA 1234567",
+            "CA",
+        ];
+        for plate in cases {
+            assert_eq!(classified(plate), (false, None, None), "{:?}", plate);
+        }
+    }
+}
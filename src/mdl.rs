@@ -0,0 +1,137 @@
+use serde::Serialize;
+use crate::date::{DateValue, LicenseDate};
+use crate::drivers_license::DriversLicenseData;
+use crate::gender::Gender;
+use crate::restriction::DriverRestriction;
+
+pub const MDL_DOC_TYPE: &str = "org.iso.18013.5.1.mDL";
+const ISSUING_AUTHORITY: &str = "Department of Transport";
+const SOUTH_AFRICA: &str = "ZA";
+
+// SA codes and the ISO 18013-1 categories that cover the same vehicles.
+const VEHICLE_CATEGORIES: [(&str, &str); 8] =
+    [("A1", "A1"), ("A", "A"), ("B", "B"), ("EB", "BE"), ("C1", "C1"), ("C", "C"), ("EC1", "C1E"), ("EC", "CE")];
+
+// Vehicle restriction 1 (automatic transmission only) is harmonised code 78.
+const AUTOMATIC_TRANSMISSION: &str = "1";
+
+/// A licence as ISO 18013-5 mDL data elements, for bridging card scans into an mDL verification stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct MdlDocument {
+    /// Always `MDL_DOC_TYPE`.
+    #[serde(rename = "docType")]
+    pub doc_type: String,
+    #[serde(rename = "nameSpaces")]
+    pub name_spaces: MdlNameSpaces,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct MdlNameSpaces {
+    #[serde(rename = "org.iso.18013.5.1")]
+    pub iso: MdlElements,
+}
+
+/// The data elements of the `org.iso.18013.5.1` namespace that a licence carries. Dates are full-date strings.
+/// There is no `portrait`: licences store the photo in a format that isn't the JPEG or JPEG 2000 mDL requires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct MdlElements {
+    pub family_name: String,
+    /// Licences only carry initials, e.g. "J A".
+    pub given_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub birth_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<String>,
+    pub issuing_country: String,
+    pub issuing_authority: String,
+    pub document_number: String,
+    pub driving_privileges: Vec<DrivingPrivilege>,
+    pub un_distinguishing_sign: String,
+    /// ISO/IEC 5218: 1 for male, 2 for female. Left out when unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sex: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct DrivingPrivilege {
+    /// The ISO 18013-1 category, e.g. "BE" for code EB. Codes without an equivalent are passed through.
+    pub vehicle_category_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub codes: Vec<PrivilegeCode>,
+}
+
+/// A harmonised restriction code, e.g. "01" for corrective lenses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct PrivilegeCode {
+    pub code: String,
+}
+
+/// Maps a parsed licence to mDL data elements. Driver restrictions become codes 01 (corrective lenses) and 03
+/// (prosthesis) on every privilege, and vehicle restriction 1 code 78 (automatic transmission); other restrictions
+/// have no harmonised equivalent and are left out.
+pub fn to_mdl(license: &DriversLicenseData) -> MdlDocument {
+    let driver_codes: Vec<&str> = license
+        .driver_restrictions
+        .iter()
+        .filter_map(|restriction| match restriction {
+            DriverRestriction::CorrectiveLenses => Some("01"),
+            DriverRestriction::ArtificialLimb => Some("03"),
+            DriverRestriction::Unknown(_) => None,
+        })
+        .collect();
+
+    let driving_privileges = license
+        .licenses
+        .iter()
+        .map(|held| {
+            let mut codes: Vec<&str> = driver_codes.clone();
+            if held.restriction.as_deref().map(str::trim) == Some(AUTOMATIC_TRANSMISSION) {
+                codes.push("78");
+            }
+            DrivingPrivilege {
+                vehicle_category_code: vehicle_category(&held.code),
+                issue_date: held.issued.as_ref().and_then(full_date),
+                codes: codes.into_iter().map(|code| PrivilegeCode { code: code.to_string() }).collect(),
+            }
+        })
+        .collect();
+
+    let country = license.license_country_of_issue.code().trim();
+    let country = if country.is_empty() { SOUTH_AFRICA } else { country };
+    MdlDocument {
+        doc_type: MDL_DOC_TYPE.to_string(),
+        name_spaces: MdlNameSpaces {
+            iso: MdlElements {
+                family_name: license.surname.trim().to_string(),
+                given_name: license.initials_list.join(" "),
+                birth_date: full_date(&license.birthdate),
+                issue_date: full_date(&license.license_issue_date),
+                expiry_date: full_date(&license.license_expiry_date),
+                issuing_country: country.to_string(),
+                issuing_authority: ISSUING_AUTHORITY.to_string(),
+                document_number: license.license_number.trim().to_string(),
+                driving_privileges,
+                un_distinguishing_sign: country.to_string(),
+                sex: Some(license.gender.gender()).filter(|gender| *gender != Gender::Unknown).map(|gender| gender.iso_5218()),
+            },
+        },
+    }
+}
+
+fn vehicle_category(code: &str) -> String {
+    let code = code.trim().to_ascii_uppercase();
+    VEHICLE_CATEGORIES.iter().find(|(sa, _)| *sa == code).map(|(_, iso)| iso.to_string()).unwrap_or(code)
+}
+
+fn full_date(value: &DateValue) -> Option<String> {
+    value.as_date().filter(LicenseDate::is_plausible).map(|date| date.to_iso_string())
+}
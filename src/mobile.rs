@@ -0,0 +1,36 @@
+use crate::error::DecodeError;
+use crate::license::{License, LicenseFormat};
+use crate::{DriversLicenseData, VehicleLicenseData};
+
+// UniFFI passes byte arrays by value, so these take `Vec<u8>` where the Rust API borrows a slice.
+
+#[uniffi::export]
+pub fn parse_license(bytes: Vec<u8>) -> Result<License, DecodeError> {
+    crate::parse_license(&bytes)
+}
+
+/// "drivers", "vehicle" or "smart_id", or null when the payload is none of them. Nothing is decrypted.
+#[uniffi::export]
+pub fn detect_format(bytes: Vec<u8>) -> Option<LicenseFormat> {
+    crate::detect_format(&bytes)
+}
+
+#[uniffi::export]
+pub fn parse_drivers_license(bytes: Vec<u8>) -> Result<DriversLicenseData, DecodeError> {
+    crate::parse_drivers_license(&bytes)
+}
+
+#[uniffi::export]
+pub fn parse_drivers_license_base64(input: String) -> Result<DriversLicenseData, DecodeError> {
+    crate::parse_drivers_license_base64(&input)
+}
+
+#[uniffi::export]
+pub fn parse_vehicle_license(bytes: Vec<u8>) -> Result<VehicleLicenseData, DecodeError> {
+    crate::parse_vehicle_license(&bytes)
+}
+
+#[uniffi::export]
+pub fn parse_vehicle_license_string(input: String) -> Result<VehicleLicenseData, DecodeError> {
+    crate::parse_vehicle_license_string(&input)
+}
@@ -0,0 +1,27 @@
+use napi::bindgen_prelude::Buffer;
+use napi::{Env, JsUnknown};
+use napi_derive::napi;
+use serde::Serialize;
+use crate::error::DecodeError;
+
+// The Buffer is borrowed from node rather than copied into wasm memory.
+
+#[napi(js_name = "parseLicense", ts_return_type = "object")]
+pub fn parse_license(env: Env, bytes: Buffer) -> napi::Result<JsUnknown, &'static str> {
+    to_js_value(env, crate::parse_license(&bytes))
+}
+
+#[napi(js_name = "parseDriversLicense", ts_return_type = "object")]
+pub fn parse_drivers_license(env: Env, bytes: Buffer) -> napi::Result<JsUnknown, &'static str> {
+    to_js_value(env, crate::parse_drivers_license(&bytes))
+}
+
+#[napi(js_name = "parseVehicleLicense", ts_return_type = "object")]
+pub fn parse_vehicle_license(env: Env, bytes: Buffer) -> napi::Result<JsUnknown, &'static str> {
+    to_js_value(env, crate::parse_vehicle_license(&bytes))
+}
+
+fn to_js_value<T: Serialize>(env: Env, result: Result<T, DecodeError>) -> napi::Result<JsUnknown, &'static str> {
+    let data = result.map_err(|e| napi::Error::new(e.code(), e.to_string()))?;
+    env.to_js_value(&data).map_err(|e| napi::Error::new("SERIALIZATION_FAILED", e.reason))
+}
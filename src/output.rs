@@ -0,0 +1,191 @@
+use std::fmt;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use crate::error::DecodeError;
+
+/// Serializes parsed data to a JSON string, optionally with camelCase keys.
+pub fn to_json_string<T: Serialize>(data: &T, camel_case: bool) -> Result<String, DecodeError> {
+    let result = if camel_case {
+        serde_json::to_value(data).and_then(|value| serde_json::to_string(&camel_case_keys(value)))
+    } else {
+        serde_json::to_string(data)
+    };
+    result.map_err(|e| DecodeError::SerializationFailed(e.to_string()))
+}
+
+/// Serializes parsed data as CBOR (RFC 8949), a compact binary form of the JSON output with the same keys. Byte
+/// fields such as the photo are CBOR byte strings rather than arrays of numbers.
+#[cfg(feature = "cbor")]
+pub fn to_cbor<T: Serialize>(data: &T) -> Result<Vec<u8>, DecodeError> {
+    let mut cbor = Vec::new();
+    ciborium::into_writer(data, &mut cbor).map_err(|e| DecodeError::SerializationFailed(e.to_string()))?;
+    Ok(cbor)
+}
+
+/// Serializes parsed data as canonical JSON: compact, keys sorted at every level, and null, empty array and empty
+/// object values dropped. Output that only differs in key order or in how absent fields are written comes out
+/// byte-for-byte the same, so it can be hashed or signed.
+pub fn to_canonical_json<T: Serialize>(data: &T) -> Result<String, DecodeError> {
+    serde_json::to_value(data)
+        .and_then(|value| serde_json::to_string(&canonicalize(value).unwrap_or(Value::Null)))
+        .map_err(|e| DecodeError::SerializationFailed(e.to_string()))
+}
+
+/// Canonical JSON with an HMAC-SHA256 over it, so a backend holding the same key can check the data came from
+/// this decoder. `data` is kept as the exact string that was signed; re-serializing it may change the bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct SignedEnvelope {
+    /// The canonical JSON, see `to_canonical_json`.
+    pub data: String,
+    /// Lowercase hex HMAC-SHA256 of `data`.
+    pub signature: String,
+}
+
+/// Serializes `data` as canonical JSON and signs it with `key`.
+pub fn sign_canonical<T: Serialize>(data: &T, key: &[u8]) -> Result<SignedEnvelope, DecodeError> {
+    let data = to_canonical_json(data)?;
+    let signature = to_hex(&hmac_sha256(key).chain_update(data.as_bytes()).finalize().into_bytes());
+    Ok(SignedEnvelope { data, signature })
+}
+
+/// Whether `envelope.signature` matches its data under `key`. The comparison is constant time.
+pub fn verify_envelope(envelope: &SignedEnvelope, key: &[u8]) -> bool {
+    let Ok(signature) = crate::input::decode_hex(&envelope.signature) else {
+        return false;
+    };
+    hmac_sha256(key).chain_update(envelope.data.as_bytes()).verify_slice(&signature).is_ok()
+}
+
+fn hmac_sha256(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The lowercase hex SHA-256 of a payload, for referencing a scan without keeping the barcode.
+pub fn payload_sha256(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+// None for values that are left out of canonical output.
+fn canonicalize(value: Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> =
+                object.into_iter().filter_map(|(key, value)| canonicalize(value).map(|value| (key, value))).collect();
+            // Sorted explicitly, as `Map` keeps insertion order when serde_json's preserve_order is enabled.
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            (!entries.is_empty()).then(|| Value::Object(entries.into_iter().collect::<Map<_, _>>()))
+        }
+        Value::Array(items) => {
+            (!items.is_empty()).then(|| Value::Array(items.into_iter().map(|item| canonicalize(item).unwrap_or(Value::Null)).collect()))
+        }
+        other => Some(other),
+    }
+}
+
+/// Serializes a byte field as bytes rather than a sequence of numbers, which the JS bindings emit as a
+/// `Uint8Array` instead of a plain array. JSON output is unaffected.
+pub(crate) fn serialize_optional_bytes<S: Serializer>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_bytes(bytes),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serializes a byte field as bytes, like `serialize_optional_bytes`.
+pub(crate) fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+/// The counterpart to `serialize_optional_bytes`, accepting bytes (e.g. a `Uint8Array`) or an array of numbers (JSON).
+pub(crate) fn deserialize_optional_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+    deserializer.deserialize_option(OptionalBytesVisitor)
+}
+
+/// Accepts bytes (e.g. a `Uint8Array`) or an array of numbers (JSON).
+pub(crate) fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    deserializer.deserialize_byte_buf(BytesVisitor)
+}
+
+struct OptionalBytesVisitor;
+
+impl<'de> Visitor<'de> for OptionalBytesVisitor {
+    type Value = Option<Vec<u8>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bytes, an array of bytes or null")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_byte_buf(BytesVisitor).map(Some)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bytes or an array of bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Renames every object key from snake_case to camelCase, e.g. `license_number` to `licenseNumber`.
+pub fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object.into_iter().map(|(key, value)| (to_camel_case(&key), camel_case_keys(value))).collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(camel_case_keys).collect()),
+        other => other,
+    }
+}
+
+pub(crate) fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper_next = !camel.is_empty();
+        } else if upper_next {
+            camel.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use crate::date::{DateValue, LicenseDate};
+
+/// A professional driving permit category, as the letters appear in the PrDP code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum PermitCategory {
+    #[serde(rename = "G")]
+    Goods,
+    #[serde(rename = "P")]
+    Passengers,
+    #[serde(rename = "D")]
+    DangerousGoods,
+}
+
+impl PermitCategory {
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'G' => Some(PermitCategory::Goods),
+            'P' => Some(PermitCategory::Passengers),
+            'D' => Some(PermitCategory::DangerousGoods),
+            _ => None,
+        }
+    }
+}
+
+/// The holder's professional driving permit: its categories paired with the permit expiry date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ProfessionalPermit {
+    pub categories: Vec<PermitCategory>,
+    pub expiry_date: Option<DateValue>,
+}
+
+impl ProfessionalPermit {
+    /// Builds the permit from the raw PrDP code and expiry. Letters other than G, P and D are ignored.
+    pub fn new(code: Option<&str>, expiry_date: Option<DateValue>) -> Option<Self> {
+        if code.is_none() && expiry_date.is_none() {
+            return None;
+        }
+        let categories = code.unwrap_or_default().chars().filter_map(PermitCategory::from_letter).collect();
+        Some(ProfessionalPermit { categories, expiry_date })
+    }
+
+    /// True when the permit covers `category` and has not expired on `date`.
+    pub fn permits(&self, category: PermitCategory, date: LicenseDate) -> bool {
+        self.categories.contains(&category)
+            && self.expiry_date.as_ref().and_then(DateValue::as_date).is_some_and(|expiry| date <= expiry)
+    }
+
+    pub fn may_carry_passengers(&self, date: LicenseDate) -> bool {
+        self.permits(PermitCategory::Passengers, date)
+    }
+
+    pub fn may_carry_goods(&self, date: LicenseDate) -> bool {
+        self.permits(PermitCategory::Goods, date)
+    }
+
+    pub fn may_carry_dangerous_goods(&self, date: LicenseDate) -> bool {
+        self.permits(PermitCategory::DangerousGoods, date)
+    }
+}
@@ -0,0 +1,62 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::Serialize;
+use crate::error::DecodeError;
+use crate::output;
+
+create_exception!(wasm_license_decoder, LicenseDecodeError, PyValueError, "A payload that couldn't be decoded. `code` holds the stable error code; `section`, `field`, `offset` and `rsa_block` say where decoding stopped.");
+
+/// Parses a 720-byte driver's licence payload into a dict.
+#[pyfunction]
+fn parse_drivers_license(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    to_dict(py, crate::parse_drivers_license(data))
+}
+
+/// Parses a vehicle licence disc payload into a dict.
+#[pyfunction]
+fn parse_vehicle_license(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    to_dict(py, crate::parse_vehicle_license(data))
+}
+
+/// Detects the payload type and returns `{"type": "drivers" | "vehicle" | "smart_id", "data": {...}}`.
+#[pyfunction]
+fn parse_license(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    to_dict(py, crate::parse_license(data))
+}
+
+/// "drivers", "vehicle" or "smart_id", or None when the payload is none of them. Nothing is decrypted.
+#[pyfunction]
+fn detect_format(data: &[u8]) -> Option<&'static str> {
+    crate::detect_format(data).map(|format| match format {
+        crate::LicenseFormat::Drivers => "drivers",
+        crate::LicenseFormat::Vehicle => "vehicle",
+        crate::LicenseFormat::SmartId => "smart_id",
+    })
+}
+
+#[pymodule]
+fn wasm_license_decoder(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("LicenseDecodeError", m.py().get_type::<LicenseDecodeError>())?;
+    m.add_function(wrap_pyfunction!(parse_drivers_license, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_vehicle_license, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_license, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_format, m)?)?;
+    Ok(())
+}
+
+// Goes through JSON so the dicts match the JSON output key for key, without a second serializer to keep in sync.
+fn to_dict<T: Serialize>(py: Python<'_>, result: Result<T, DecodeError>) -> PyResult<PyObject> {
+    let json = result.and_then(|data| output::to_json_string(&data, false)).map_err(|e| to_py_error(py, &e))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+fn to_py_error(py: Python<'_>, error: &DecodeError) -> PyErr {
+    let err = LicenseDecodeError::new_err(error.to_string());
+    let _ = err.value(py).setattr("code", error.code());
+    let _ = err.value(py).setattr("section", error.section().map(|section| section.id()));
+    let _ = err.value(py).setattr("field", error.field());
+    let _ = err.value(py).setattr("offset", error.offset());
+    let _ = err.value(py).setattr("rsa_block", error.rsa_block());
+    err
+}
@@ -0,0 +1,111 @@
+use crate::error::{DecodeError, Section};
+
+/// A cursor over a byte buffer. Every read is bounds-checked and fails with the section and field being read and
+/// its offset.
+pub struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    section: Section,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, position: 0, section: Section::Header }
+    }
+
+    /// The section that later reads are reported in when they fail.
+    pub fn set_section(&mut self, section: Section) {
+        self.section = section;
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves to `position`, clamped to the end of the data.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.data.len());
+    }
+
+    /// Advances by `count` bytes, stopping at the end of the data.
+    pub fn skip(&mut self, count: usize) {
+        self.seek(self.position.saturating_add(count));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    pub fn take_byte(&mut self, field: &'static str) -> Result<u8, DecodeError> {
+        let byte = *self.data.get(self.position).ok_or(self.truncated(field))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn take_bytes(&mut self, count: usize, field: &'static str) -> Result<&'a [u8], DecodeError> {
+        let end = self.position.checked_add(count).ok_or(self.truncated(field))?;
+        let bytes = self.data.get(self.position..end).ok_or(self.truncated(field))?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Reads up to the next of `delimiters` and consumes it. Fails if the data ends first.
+    pub fn read_until_delimiter(&mut self, delimiters: &[u8], field: &'static str) -> Result<(&'a [u8], u8), DecodeError> {
+        match self.read_until_delimiter_or_end(delimiters) {
+            (bytes, Some(delimiter)) => Ok((bytes, delimiter)),
+            (_, None) => Err(self.truncated(field)),
+        }
+    }
+
+    /// Reads up to the next of `delimiters` and consumes it, or to the end of the data if there is none.
+    pub fn read_until_delimiter_or_end(&mut self, delimiters: &[u8]) -> (&'a [u8], Option<u8>) {
+        let rest = self.remaining();
+        match rest.iter().position(|b| delimiters.contains(b)) {
+            Some(length) => {
+                self.position += length + 1;
+                (&rest[..length], Some(rest[length]))
+            }
+            None => {
+                self.position = self.data.len();
+                (rest, None)
+            }
+        }
+    }
+
+    /// Reads a nibble-packed section up to `terminator`, or to the end of the data, and consumes it.
+    pub fn read_nibbles(&mut self, terminator: u8) -> NibbleReader<'a> {
+        let offset = self.position;
+        let (bytes, _) = self.read_until_delimiter_or_end(&[terminator]);
+        NibbleReader { bytes, offset, position: 0 }
+    }
+
+    fn truncated(&self, field: &'static str) -> DecodeError {
+        DecodeError::TruncatedData { section: self.section, field, offset: self.position, rsa_block: None }
+    }
+}
+
+/// Reads a packed section one 4-bit nibble at a time, high nibble first.
+pub struct NibbleReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    position: usize,
+}
+
+impl NibbleReader<'_> {
+    pub fn take(&mut self, field: &'static str) -> Result<u8, DecodeError> {
+        let byte_index = self.position / 2;
+        let byte = self.bytes.get(byte_index).ok_or(DecodeError::TruncatedData {
+            section: Section::Nibbles,
+            field,
+            offset: self.offset + byte_index,
+            rsa_block: None,
+        })?;
+        let nibble = if self.position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0f };
+        self.position += 1;
+        Ok(nibble)
+    }
+}
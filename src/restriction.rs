@@ -0,0 +1,65 @@
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// A driver restriction, packed as one digit per slot in the restriction section. A zero digit means no restriction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum DriverRestriction {
+    CorrectiveLenses,
+    ArtificialLimb,
+    Unknown(u8),
+}
+
+impl DriverRestriction {
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => None,
+            1 => Some(DriverRestriction::CorrectiveLenses),
+            2 => Some(DriverRestriction::ArtificialLimb),
+            other => Some(DriverRestriction::Unknown(other)),
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            DriverRestriction::CorrectiveLenses => 1,
+            DriverRestriction::ArtificialLimb => 2,
+            DriverRestriction::Unknown(code) => *code,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DriverRestriction::CorrectiveLenses => "corrective lenses",
+            DriverRestriction::ArtificialLimb => "artificial limb",
+            DriverRestriction::Unknown(_) => "unknown",
+        }
+    }
+}
+
+// Serialized as `{ code, description }` so JS callers get both the raw digit and its meaning.
+impl Serialize for DriverRestriction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DriverRestriction", 2)?;
+        state.serialize_field("code", &self.code().to_string())?;
+        state.serialize_field("description", self.description())?;
+        state.end()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct DriverRestrictionCode {
+    code: String,
+}
+
+// Reads back the serialized `{ code, description }`. The description is derived from the code, so it is ignored.
+impl<'de> Deserialize<'de> for DriverRestriction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let DriverRestrictionCode { code } = DriverRestrictionCode::deserialize(deserializer)?;
+        code.trim()
+            .parse()
+            .ok()
+            .and_then(DriverRestriction::from_code)
+            .ok_or_else(|| D::Error::custom(format!("invalid driver restriction code {}", code)))
+    }
+}
@@ -0,0 +1,26 @@
+use schemars::JsonSchema;
+use serde_json::Value;
+use crate::drivers_license::DriversLicenseData;
+use crate::license::{LicenseFormat, SCHEMA_VERSION};
+use crate::smart_id::SmartIdData;
+use crate::vehicle_license::VehicleLicenseData;
+
+/// The `{ code, description }` shape that driver restrictions and ID document types serialize to.
+#[derive(JsonSchema)]
+#[allow(dead_code)] // Only used for its schema.
+pub(crate) struct CodedValue {
+    code: String,
+    description: String,
+}
+
+/// A JSON Schema for a format's parsed output, with default (snake_case) keys. The output schema version is under
+/// `x-schema-version`, see `license::SCHEMA_VERSION`.
+pub fn schema(format: LicenseFormat) -> Value {
+    let mut schema = match format {
+        LicenseFormat::Drivers => schemars::schema_for!(DriversLicenseData),
+        LicenseFormat::Vehicle => schemars::schema_for!(VehicleLicenseData),
+        LicenseFormat::SmartId => schemars::schema_for!(SmartIdData),
+    };
+    schema.insert("x-schema-version".to_string(), SCHEMA_VERSION.into());
+    schema.into()
+}
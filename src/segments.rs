@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use serde::Deserialize;
+use crate::error::DecodeError;
+use crate::output;
+
+// A Macro PDF417 segment index is at most 99998, so a file has at most 99999 segments.
+const MAX_SEGMENTS: u32 = 99_999;
+
+/// One Macro PDF417 symbol, as reported by the scanner: its position in the file and its data.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct MacroSegment {
+    /// Zero-based segment index.
+    pub index: u32,
+    #[serde(deserialize_with = "output::deserialize_bytes")]
+    #[cfg_attr(feature = "wasm", tsify(type = "Uint8Array"))]
+    pub data: Vec<u8>,
+    /// The file ID shared by every segment of one licence, when the scanner reports it.
+    #[serde(default)]
+    pub file_id: Option<String>,
+    /// The optional segment count field of the control block.
+    #[serde(default)]
+    pub segment_count: Option<u32>,
+    /// Set on the segment carrying the last-segment terminator.
+    #[serde(default)]
+    pub is_last: bool,
+}
+
+/// Collects Macro PDF417 segments in any order and joins them into the payload once all have arrived.
+#[derive(Debug, Default, Clone)]
+pub struct SegmentAssembler {
+    file_id: Option<String>,
+    segment_count: Option<u32>,
+    segments: BTreeMap<u32, Vec<u8>>,
+}
+
+impl SegmentAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a segment. Repeats of a segment already held are ignored, but segments from another file,
+    /// a repeat with different data, or an index outside the segment count are rejected.
+    pub fn add(&mut self, segment: MacroSegment) -> Result<(), DecodeError> {
+        if segment.index >= MAX_SEGMENTS {
+            return Err(DecodeError::SegmentMismatch(format!("segment index {} is above the Macro PDF417 maximum", segment.index)));
+        }
+        if let Some(count) = segment.segment_count.filter(|&count| count == 0 || count > MAX_SEGMENTS) {
+            return Err(DecodeError::SegmentMismatch(format!("segment {} reports an impossible {} segments", segment.index, count)));
+        }
+
+        if let (Some(expected), Some(file_id)) = (&self.file_id, &segment.file_id)
+            && expected != file_id
+        {
+            return Err(DecodeError::SegmentMismatch(format!("segment {} belongs to file {}, not {}", segment.index, file_id, expected)));
+        }
+
+        let segment_count = segment.segment_count.or(segment.is_last.then(|| segment.index.checked_add(1)).flatten());
+        if let (Some(expected), Some(count)) = (self.segment_count, segment_count)
+            && expected != count
+        {
+            return Err(DecodeError::SegmentMismatch(format!("segment {} reports {} segments, not {}", segment.index, count, expected)));
+        }
+
+        // Segments already held must also fit a count this segment is the first to report.
+        let last_index = self.segments.keys().next_back().map_or(segment.index, |&last| last.max(segment.index));
+        if let Some(count) = self.segment_count.or(segment_count)
+            && last_index >= count
+        {
+            return Err(DecodeError::SegmentMismatch(format!("segment {} is outside the {} segments of the file", last_index, count)));
+        }
+
+        if let Some(held) = self.segments.get(&segment.index) {
+            if *held != segment.data {
+                return Err(DecodeError::SegmentMismatch(format!("segment {} was scanned twice with different data", segment.index)));
+            }
+            return Ok(());
+        }
+
+        self.file_id = self.file_id.take().or(segment.file_id);
+        self.segment_count = self.segment_count.or(segment_count);
+        self.segments.insert(segment.index, segment.data);
+        Ok(())
+    }
+
+    /// The total number of segments, once the count or the last segment has been seen.
+    pub fn segment_count(&self) -> Option<u32> {
+        self.segment_count
+    }
+
+    pub fn received(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Indices still to be scanned. Only those below the highest index seen until the count is known.
+    pub fn missing(&self) -> Vec<u32> {
+        let end = self.segment_count.or(self.segments.keys().next_back().and_then(|last| last.checked_add(1))).unwrap_or(0);
+        (0..end).filter(|index| !self.segments.contains_key(index)).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.segment_count.is_some() && self.missing().is_empty()
+    }
+
+    /// The segments' data joined in index order, ready for `parse_license`.
+    pub fn assemble(&self) -> Result<Vec<u8>, DecodeError> {
+        if !self.is_complete() {
+            return Err(DecodeError::IncompleteSegments { missing: self.missing(), segment_count: self.segment_count });
+        }
+        Ok(self.segments.values().flatten().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(index: u32, data: &[u8]) -> MacroSegment {
+        MacroSegment { index, data: data.to_vec(), file_id: Some("017".to_string()), segment_count: None, is_last: false }
+    }
+
+    fn last(index: u32, data: &[u8]) -> MacroSegment {
+        MacroSegment { is_last: true, ..segment(index, data) }
+    }
+
+    #[test]
+    fn out_of_order_segments_assemble_in_index_order() {
+        let mut assembler = SegmentAssembler::new();
+        assembler.add(last(2, b"ef")).expect("segment 2 is accepted");
+        assembler.add(segment(0, b"ab")).expect("segment 0 is accepted");
+        assert_eq!(assembler.missing(), [1]);
+        assert!(matches!(assembler.assemble(), Err(DecodeError::IncompleteSegments { .. })));
+
+        assembler.add(segment(1, b"cd")).expect("segment 1 is accepted");
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.assemble().expect("all segments arrived"), b"abcdef");
+    }
+
+    #[test]
+    fn duplicates_are_ignored_unless_their_data_differs() {
+        let mut assembler = SegmentAssembler::new();
+        assembler.add(segment(0, b"ab")).expect("segment 0 is accepted");
+        assembler.add(segment(0, b"ab")).expect("an identical repeat is ignored");
+        assert_eq!(assembler.received(), 1);
+        assert!(matches!(assembler.add(segment(0, b"xx")), Err(DecodeError::SegmentMismatch(_))));
+    }
+
+    #[test]
+    fn conflicting_counts_and_files_are_rejected() {
+        let mut assembler = SegmentAssembler::new();
+        assembler.add(MacroSegment { segment_count: Some(3), ..segment(0, b"ab") }).expect("segment 0 is accepted");
+        assert!(matches!(assembler.add(MacroSegment { segment_count: Some(4), ..segment(1, b"cd") }), Err(DecodeError::SegmentMismatch(_))));
+        assert!(matches!(assembler.add(last(1, b"cd")), Err(DecodeError::SegmentMismatch(_))));
+        assert!(matches!(assembler.add(MacroSegment { file_id: Some("018".to_string()), ..segment(1, b"cd") }), Err(DecodeError::SegmentMismatch(_))));
+        assert_eq!(assembler.segment_count(), Some(3));
+    }
+
+    #[test]
+    fn out_of_range_segments_are_rejected() {
+        let mut assembler = SegmentAssembler::new();
+        assembler.add(last(1, b"cd")).expect("segment 1 is accepted");
+        assert!(matches!(assembler.add(segment(2, b"ef")), Err(DecodeError::SegmentMismatch(_))));
+        assert!(matches!(assembler.add(last(u32::MAX, b"ef")), Err(DecodeError::SegmentMismatch(_))));
+        assert!(matches!(assembler.add(MacroSegment { segment_count: Some(u32::MAX), ..segment(0, b"ab") }), Err(DecodeError::SegmentMismatch(_))));
+
+        let mut assembler = SegmentAssembler::new();
+        assembler.add(segment(5, b"ef")).expect("segment 5 is accepted");
+        assert!(matches!(assembler.add(last(2, b"cd")), Err(DecodeError::SegmentMismatch(_))));
+        assert_eq!(assembler.missing(), [0, 1, 2, 3, 4]);
+    }
+}
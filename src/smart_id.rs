@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use crate::date::{self, DateFormat, DateValue, LicenseDate};
+use crate::drivers_license;
+use crate::error::DecodeError;
+use crate::gender::{Gender, GenderFormat, GenderValue};
+use crate::id_number::{self, IdNumberValidation};
+use crate::output;
+use crate::warning::Warning;
+
+const MIN_PARTS: usize = 9;
+const ID_NUMBER_PART: usize = 4;
+
+/// The PDF417 on the back of the South African smart ID card: plain text with '|' between the fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct SmartIdData {
+    pub surname: String,
+    pub names: String,
+    pub gender: GenderValue,
+    /// "RSA" for citizens. Home Affairs uses three-letter codes here, not the ISO 3166 alpha-2 codes of licences.
+    pub nationality: String,
+    pub id_number: String,
+    pub id_number_validation: Option<IdNumberValidation>,
+    pub birthdate: DateValue,
+    pub country_of_birth: String,
+    /// e.g. "CITIZEN" or "PERMANENT RESIDENT".
+    pub citizenship_status: String,
+    pub issue_date: DateValue,
+    /// Any fields after the issue date, kept as they are since their meaning isn't documented.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// SHA-256 of the payload as hex, when the `fingerprint` option is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(default, rename = "SmartIdParseOptions")]
+pub struct ParseOptions {
+    /// How the birthdate and issue date are emitted.
+    #[serde(alias = "dateFormat")]
+    pub date_format: DateFormat,
+    /// How the holder's gender is emitted.
+    #[serde(alias = "genderFormat")]
+    pub gender_format: GenderFormat,
+    /// Mask personal identifiers in the output, see `SmartIdData::redact`.
+    pub redact: bool,
+    /// Add `payload_sha256`, a hash of the payload for deduplication and audit trails. It is kept when redacting.
+    pub fingerprint: bool,
+    /// Emit camelCase keys (`idNumber`) instead of snake_case. Only affects the JS bindings.
+    #[serde(alias = "camelCase")]
+    pub camel_case: bool,
+}
+
+impl SmartIdData {
+    pub fn from_parts(parts: &[&str], gender_format: GenderFormat) -> Result<Self, DecodeError> {
+        if parts.len() < MIN_PARTS {
+            return Err(DecodeError::InsufficientParts);
+        }
+        let gender = match parts[2].trim() {
+            "M" => Gender::Male,
+            "F" => Gender::Female,
+            _ => Gender::Unknown,
+        };
+        let id_number = parts[ID_NUMBER_PART].trim().to_string();
+        let birthdate = DateValue::from(parts[5].trim().to_string());
+        Ok(SmartIdData {
+            surname: parts[0].trim().to_string(),
+            names: parts[1].trim().to_string(),
+            gender: GenderValue::new(gender, gender_format),
+            nationality: parts[3].trim().to_string(),
+            id_number_validation: id_number::validate(&id_number, birthdate.as_date(), gender),
+            id_number,
+            birthdate,
+            country_of_birth: parts[6].trim().to_string(),
+            citizenship_status: parts[7].trim().to_string(),
+            issue_date: parts[8].trim().to_string().into(),
+            extra_fields: parts[MIN_PARTS..].iter().map(|part| part.trim().to_string()).collect(),
+            warnings: Vec::new(),
+            payload_sha256: None,
+        })
+    }
+
+    /// Masks personal identifiers like `DriversLicenseData::redact`: the ID number keeps its first four digits and
+    /// the birthdate and any warning that echoes it are dropped.
+    pub fn redact(&mut self) {
+        self.id_number = drivers_license::mask(&self.id_number, 4, 0);
+        self.birthdate = DateValue::default();
+        self.warnings.retain(|warning| !warning.reveals_birthdate());
+    }
+
+    /// The card's issue date, when it parses to a real calendar date.
+    pub fn issued(&self) -> Option<LicenseDate> {
+        self.issue_date.as_date().filter(LicenseDate::is_plausible)
+    }
+
+    /// Smart ID cards don't expire, so a card is valid from its issue date on.
+    pub fn is_valid_on(&self, date: LicenseDate) -> Option<bool> {
+        self.issued().map(|issued| issued <= date)
+    }
+}
+
+/// Whether `bytes` look like a smart ID card: '|'-separated text with a 13 digit ID number in the fifth field.
+pub(crate) fn is_smart_id(bytes: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return false;
+    };
+    let parts: Vec<&str> = text.split('|').collect();
+    parts.len() >= MIN_PARTS && {
+        let id_number = parts[ID_NUMBER_PART].trim();
+        id_number.len() == 13 && id_number.bytes().all(|b| b.is_ascii_digit())
+    }
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<SmartIdData, DecodeError> {
+    parse_bytes_with_options(bytes, ParseOptions::default())
+}
+
+pub fn parse_bytes_with_options(bytes: &[u8], options: ParseOptions) -> Result<SmartIdData, DecodeError> {
+    let parts: Vec<&str> = std::str::from_utf8(bytes)?.trim_end_matches(['\r', '\n']).split('|').collect();
+    let mut card = SmartIdData::from_parts(&parts, options.gender_format)?;
+    date::apply_format("birthdate", &mut card.birthdate, options.date_format, &mut card.warnings);
+    date::apply_format("issue_date", &mut card.issue_date, options.date_format, &mut card.warnings);
+    if options.redact {
+        card.redact();
+    }
+    if options.fingerprint {
+        card.payload_sha256 = Some(output::payload_sha256(bytes));
+    }
+    Ok(card)
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+/// How text fields in the driver's licence payload are decoded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    /// ISO 8859-1, one byte per character.
+    #[default]
+    Latin1,
+    /// Windows code page 1252, which differs from Latin-1 in 0x80-0x9F (e.g. 0x80 is '€').
+    Windows1252,
+    /// UTF-8, with invalid sequences replaced by U+FFFD.
+    Utf8,
+}
+
+impl TextEncoding {
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            TextEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+            TextEncoding::Windows1252 => bytes.iter().map(|&b| windows_1252_char(b)).collect(),
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+// 0x80-0x9F in Windows-1252. Unassigned bytes map to the matching C1 control, as browsers do.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{0081}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008d}', '\u{017d}', '\u{008f}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{0153}', '\u{009d}', '\u{017e}', '\u{0178}',
+];
+
+/// The 0x80-0x9F byte for a Windows-1252 character outside Latin-1, e.g. '€' to 0x80.
+pub(crate) fn windows_1252_byte(c: char) -> Option<u8> {
+    WINDOWS_1252_HIGH.iter().position(|&high| high == c).map(|index| 0x80 + index as u8)
+}
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9f => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
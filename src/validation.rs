@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use crate::country;
+use crate::date::{DateValue, LicenseDate};
+use crate::drivers_license::{self, DriversLicenseData};
+use crate::gender::Gender;
+
+/// One cross-field check on a decoded licence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct ValidationCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct ValidationReport {
+    /// True when any check failed and a person should look at the card.
+    pub needs_review: bool,
+    pub checks: Vec<ValidationCheck>,
+}
+
+/// How far a lenient parse of partially corrupt data can be trusted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ParseConfidence {
+    /// From 0 to 1: the share of key fields that were read, scaled by the share of cross-field checks that passed.
+    pub overall: f64,
+    /// False when corrupt data stopped the parse, leaving the fields after it empty.
+    pub complete: bool,
+    /// Fields that weren't read or are involved in a failed check.
+    pub suspect_fields: Vec<String>,
+    /// True when the parse is incomplete or any check failed.
+    pub needs_review: bool,
+}
+
+// The fields each check depends on, so a failed check marks them as suspect.
+const CHECK_FIELDS: [(&str, &[&str]); 7] = [
+    ("id_check_digit", &["id_number"]),
+    ("id_birthdate", &["id_number", "birthdate"]),
+    ("id_gender", &["id_number", "gender"]),
+    ("date_order", &["birthdate", "license_issue_date", "license_expiry_date"]),
+    ("country_codes", &["id_country_of_issue", "license_country_of_issue"]),
+    ("code_dates", &["licenses"]),
+    ("issue_ages", &["licenses"]),
+];
+
+impl ValidationCheck {
+    fn new(name: &'static str, passed: bool, reason: impl Into<String>) -> Self {
+        ValidationCheck { name, passed, reason: reason.into() }
+    }
+}
+
+pub fn validate(license: &DriversLicenseData) -> ValidationReport {
+    let checks = vec![
+        check_id_number(license),
+        check_id_birthdate(license),
+        check_id_gender(license),
+        check_date_order(license),
+        check_countries(license),
+        check_code_dates(license),
+        check_issue_ages(license),
+    ];
+    ValidationReport { needs_review: checks.iter().any(|check| !check.passed), checks }
+}
+
+/// Scores a lenient parse by which key fields were read and how many cross-field checks pass.
+pub(crate) fn parse_confidence(license: &DriversLicenseData, complete: bool) -> ParseConfidence {
+    let fields = [
+        ("surname", !license.surname.is_empty()),
+        ("initials", !license.initials.is_empty()),
+        ("id_country_of_issue", !license.id_country_of_issue.code().is_empty()),
+        ("license_country_of_issue", !license.license_country_of_issue.code().is_empty()),
+        ("license_number", !license.license_number.is_empty()),
+        ("id_number", !license.id_number.is_empty()),
+        ("birthdate", !license.birthdate.is_empty()),
+        ("license_issue_date", !license.license_issue_date.is_empty()),
+        ("license_expiry_date", !license.license_expiry_date.is_empty()),
+        ("gender", license.gender.gender() != Gender::Unknown),
+    ];
+    let report = validate(license);
+
+    let mut suspect_fields: Vec<String> = Vec::new();
+    let failed_fields = report
+        .checks
+        .iter()
+        .filter(|check| !check.passed)
+        .filter_map(|check| CHECK_FIELDS.iter().find(|(name, _)| *name == check.name))
+        .flat_map(|(_, fields)| fields.iter().copied());
+    for field in fields.iter().filter(|(_, read)| !read).map(|(field, _)| *field).chain(failed_fields) {
+        if !suspect_fields.iter().any(|suspect| suspect == field) {
+            suspect_fields.push(field.to_string());
+        }
+    }
+
+    let fields_read = fields.iter().filter(|(_, read)| *read).count();
+    let checks_passed = report.checks.iter().filter(|check| check.passed).count();
+    ParseConfidence {
+        overall: fields_read as f64 / fields.len() as f64 * checks_passed as f64 / report.checks.len() as f64,
+        complete,
+        suspect_fields,
+        needs_review: !complete || report.needs_review,
+    }
+}
+
+const NOT_SA_ID: &str = "Not a 13 digit South African ID number, so it was not checked";
+
+fn check_id_number(license: &DriversLicenseData) -> ValidationCheck {
+    match &license.id_number_validation {
+        Some(validation) if validation.check_digit_valid => ValidationCheck::new("id_check_digit", true, "Check digit is valid"),
+        Some(_) => ValidationCheck::new("id_check_digit", false, "Check digit does not match the ID number"),
+        None => ValidationCheck::new("id_check_digit", true, NOT_SA_ID),
+    }
+}
+
+fn check_id_birthdate(license: &DriversLicenseData) -> ValidationCheck {
+    match &license.id_number_validation {
+        Some(validation) if validation.birthdate_matches => ValidationCheck::new("id_birthdate", true, "ID number matches the birthdate"),
+        Some(_) => ValidationCheck::new("id_birthdate", false, "ID number does not match the birthdate"),
+        None => ValidationCheck::new("id_birthdate", true, NOT_SA_ID),
+    }
+}
+
+fn check_id_gender(license: &DriversLicenseData) -> ValidationCheck {
+    match &license.id_number_validation {
+        Some(validation) if validation.gender_matches => ValidationCheck::new("id_gender", true, "ID number matches the gender"),
+        Some(_) => ValidationCheck::new("id_gender", false, "ID number does not match the gender"),
+        None => ValidationCheck::new("id_gender", true, NOT_SA_ID),
+    }
+}
+
+fn plausible(date: &DateValue) -> Option<LicenseDate> {
+    date.as_date().filter(LicenseDate::is_plausible)
+}
+
+fn check_date_order(license: &DriversLicenseData) -> ValidationCheck {
+    let name = "date_order";
+    let (Some(birthdate), Some(issued), Some(expiry)) =
+        (plausible(&license.birthdate), plausible(&license.license_issue_date), plausible(&license.license_expiry_date))
+    else {
+        return ValidationCheck::new(name, false, "Birthdate, issue date or expiry date is missing or invalid");
+    };
+
+    if birthdate >= issued {
+        return ValidationCheck::new(name, false, format!("Issue date {} is not after the birthdate {}", issued, birthdate));
+    }
+    if issued > expiry {
+        return ValidationCheck::new(name, false, format!("Issue date {} is after the expiry date {}", issued, expiry));
+    }
+    let mut code_dates = license.licenses.iter().filter_map(|code| code.issued.as_ref().and_then(plausible));
+    if let Some(date) = code_dates.find(|&date| date <= birthdate || date > expiry) {
+        return ValidationCheck::new(name, false, format!("A licence code issue date {} falls outside the holder's lifetime or the card's validity", date));
+    }
+    ValidationCheck::new(name, true, "Dates are in order")
+}
+
+fn check_countries(license: &DriversLicenseData) -> ValidationCheck {
+    let invalid: Vec<&str> = [license.id_country_of_issue.code(), license.license_country_of_issue.code()]
+        .into_iter()
+        .filter(|code| country::country_name(code).is_none())
+        .collect();
+    if invalid.is_empty() {
+        ValidationCheck::new("country_codes", true, "Country codes are ISO 3166 codes")
+    } else {
+        ValidationCheck::new("country_codes", false, format!("Unexpected country codes: {}", invalid.join(", ")))
+    }
+}
+
+fn check_code_dates(license: &DriversLicenseData) -> ValidationCheck {
+    let missing: Vec<&str> =
+        license.licenses.iter().filter(|code| code.issued.is_none()).map(|code| code.code.as_str()).collect();
+    if missing.is_empty() {
+        ValidationCheck::new("code_dates", true, "Every licence code has an issue date")
+    } else {
+        ValidationCheck::new("code_dates", false, format!("No issue date for licence codes: {}", missing.join(", ")))
+    }
+}
+
+fn check_issue_ages(license: &DriversLicenseData) -> ValidationCheck {
+    let problems = drivers_license::issue_age_problems(license);
+    if problems.is_empty() {
+        ValidationCheck::new("issue_ages", true, "Every licence code was issued at or above its minimum age")
+    } else {
+        ValidationCheck::new("issue_ages", false, problems.join("; "))
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use crate::drivers_license::DriversLicenseData;
+use crate::mdl::{self, MdlElements};
+
+const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+const VDL_CONTEXT: &str = "https://w3id.org/vdl/v2";
+
+/// An unsigned W3C Verifiable Credential (data model 2.0) for a licence, using the Verifiable Driver's Licence
+/// vocabulary. The issuer service adds `issuer` and the proof when it signs it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct UnsignedCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    /// The licence issue date at midnight UTC.
+    #[serde(rename = "validFrom", skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    /// The licence expiry date at midnight UTC.
+    #[serde(rename = "validUntil", skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<String>,
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: LicensedDriver,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct LicensedDriver {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(rename = "driversLicense")]
+    pub drivers_license: CredentialLicense,
+}
+
+/// The ISO 18013-5 data elements from `mdl::to_mdl`, typed as an `Iso18013DriversLicense`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct CredentialLicense {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub elements: MdlElements,
+}
+
+/// Maps a parsed licence to an unsigned credential, with `validFrom` and `validUntil` from its issue and expiry dates.
+pub fn to_credential(license: &DriversLicenseData) -> UnsignedCredential {
+    let elements = mdl::to_mdl(license).name_spaces.iso;
+    let midnight = |date: &Option<String>| date.as_ref().map(|date| format!("{}T00:00:00Z", date));
+    UnsignedCredential {
+        context: vec![CREDENTIALS_CONTEXT.to_string(), VDL_CONTEXT.to_string()],
+        types: vec!["VerifiableCredential".to_string(), "Iso18013DriversLicenseCredential".to_string()],
+        valid_from: midnight(&elements.issue_date),
+        valid_until: midnight(&elements.expiry_date),
+        credential_subject: LicensedDriver {
+            kind: "LicensedDriver".to_string(),
+            drivers_license: CredentialLicense { kind: "Iso18013DriversLicense".to_string(), elements },
+        },
+    }
+}
@@ -0,0 +1,61 @@
+use crate::date::LicenseDate;
+use crate::drivers_license::DriversLicenseData;
+use crate::gender::Gender;
+
+// RFC 6350 wants lines folded at 75 octets.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A vCard 4.0 (RFC 6350) for the licence holder, e.g. for pushing scans into a visitor-management contact directory.
+/// It has the name, birthday and gender, with the licence number and codes in the custom `X-SA-LICENSE-NUMBER` and
+/// `X-SA-LICENSE-CODES` properties. A redacted licence gives a card without the birthday.
+pub fn to_vcard(license: &DriversLicenseData) -> String {
+    // Licences only carry initials: the first is the given name and the rest additional names.
+    let given = license.initials_list.first().map(|initial| escape(initial)).unwrap_or_default();
+    let additional = escape_list(license.initials_list.get(1..).unwrap_or_default());
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:4.0".to_string(),
+        format!("FN:{}", escape(&license.full_name())),
+        format!("N:{};{};{};;", escape(&license.surname), given, additional),
+    ];
+    if let Some(birthdate) = license.birthdate.as_date().filter(LicenseDate::is_plausible) {
+        lines.push(format!("BDAY:{:04}{:02}{:02}", birthdate.year, birthdate.month, birthdate.day));
+    }
+    match license.gender.gender() {
+        Gender::Male => lines.push("GENDER:M".to_string()),
+        Gender::Female => lines.push("GENDER:F".to_string()),
+        Gender::Unknown => {}
+    }
+    if !license.license_number.is_empty() {
+        lines.push(format!("X-SA-LICENSE-NUMBER:{}", escape(&license.license_number)));
+    }
+    if !license.vehicle_codes.is_empty() {
+        lines.push(format!("X-SA-LICENSE-CODES:{}", escape_list(&license.vehicle_codes)));
+    }
+    lines.push("END:VCARD".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+fn escape(text: &str) -> String {
+    text.trim().replace('\\', "\\\\").replace(',', "\\,").replace(';', "\\;").replace('\n', "\\n").replace('\r', "")
+}
+
+fn escape_list(values: &[String]) -> String {
+    values.iter().map(|value| escape(value)).collect::<Vec<_>>().join(",")
+}
+
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            // The continuation line's leading space counts towards its length.
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
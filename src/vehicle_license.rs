@@ -1,69 +1,435 @@
-use std::error::Error;
-use std::fmt;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use crate::date::{self, DateFormat, DateValue, LicenseDate};
+use crate::error::DecodeError;
+use crate::license_plate;
+use crate::output;
+use crate::reader::Reader;
+use crate::warning::Warning;
 
-#[derive(Debug, Serialize)]
-#[allow(dead_code)]
-pub struct VehicleLicenseData {
-    make: String,
-    description: String,
-    color: String,
-    license_number: String,
-    vin_number: String,
-    vehicle_register_number: String,
-    engine_number: String,
-    expiry_date: String,
-}
-
-#[derive(Debug)]
-pub enum ParseError {
-    InvalidUtf8(std::string::FromUtf8Error),
-    InsufficientParts,
-}
-
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ParseError::InvalidUtf8(err) => write!(f, "Invalid UTF-8: {}", err),
-            ParseError::InsufficientParts => write!(f, "Input data does not contain enough parts"),
-        }
+/// Which '%'-separated part holds each field. The first part is empty, as discs start with a '%'.
+struct DiscLayout {
+    disc_type: usize,
+    authority_code: usize,
+    control_number: usize,
+    disc_number: usize,
+    license_number: usize,
+    vehicle_register_number: usize,
+    description: usize,
+    make: usize,
+    model: usize,
+    color: usize,
+    vin_number: usize,
+    engine_number: usize,
+    expiry_date: usize,
+    /// Where the GVM, tare, persons seated, persons standing and vehicle category start, when present.
+    optional_parts: usize,
+}
+
+impl DiscLayout {
+    // Discs end with a '%', leaving an empty part after the expiry date. Trailer discs may leave it off.
+    fn min_parts(&self, is_trailer: bool) -> usize {
+        if is_trailer { self.expiry_date + 1 } else { self.expiry_date + 2 }
     }
 }
 
-impl Error for ParseError {}
+// The standard layout, the only one confirmed from sample discs. Part 4 is a single digit whose meaning isn't
+// documented, so it is left unmapped.
+const LAYOUT: DiscLayout = DiscLayout {
+    disc_type: 1,
+    authority_code: 2,
+    control_number: 3,
+    disc_number: 5,
+    license_number: 6,
+    vehicle_register_number: 7,
+    description: 8,
+    make: 9,
+    model: 10,
+    color: 11,
+    vin_number: 12,
+    engine_number: 13,
+    expiry_date: 14,
+    optional_parts: 15,
+};
 
-impl From<std::string::FromUtf8Error> for ParseError {
-    fn from(err: std::string::FromUtf8Error) -> Self {
-        ParseError::InvalidUtf8(err)
-    }
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct VehicleLicenseData {
+    pub disc_type: String,
+    pub authority_code: String,
+    pub control_number: String,
+    pub disc_number: String,
+    pub make: String,
+    pub model: String,
+    pub make_model: String,
+    pub description: String,
+    /// Set when the description names a trailer. Trailers have no engine, so `engine_number` is usually empty.
+    #[serde(default)]
+    pub is_trailer: bool,
+    pub color: String,
+    pub license_number: String,
+    pub license_number_valid: bool,
+    pub province: Option<String>,
+    pub registration_authority: Option<String>,
+    pub vin_number: String,
+    pub vehicle_register_number: String,
+    pub engine_number: String,
+    pub expiry_date: DateValue,
+    pub gvm: Option<u32>,
+    pub gvm_raw: Option<String>,
+    pub tare: Option<u32>,
+    pub tare_raw: Option<String>,
+    pub persons_seated: Option<u32>,
+    pub persons_standing: Option<u32>,
+    pub vehicle_category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// SHA-256 of the payload as hex, when the `fingerprint` option is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(default, rename = "VehicleParseOptions")]
+pub struct ParseOptions {
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of rejecting the disc.
+    #[serde(alias = "lossyUtf8")]
+    pub lossy_utf8: bool,
+    /// How the expiry date is emitted.
+    #[serde(alias = "dateFormat")]
+    pub date_format: DateFormat,
+    /// Add `payload_sha256`, a hash of the payload for deduplication and audit trails. It is kept when redacting.
+    pub fingerprint: bool,
+    /// Emit camelCase keys (`licenseNumber`) instead of snake_case. Only affects the JS bindings.
+    #[serde(alias = "camelCase")]
+    pub camel_case: bool,
 }
 
 impl VehicleLicenseData {
-    pub fn from_parts(parts: &[&str]) -> Result<Self, ParseError> {
-        if parts.len() < 16 {
-            return Err(ParseError::InsufficientParts);
+    pub fn from_parts(parts: &[&str]) -> Result<Self, DecodeError> {
+        if !matches_layout(parts) {
+            return Err(DecodeError::InsufficientParts);
         }
+        let layout = &LAYOUT;
+        let is_trailer = is_trailer_description(parts[layout.description]);
+        let plate = license_plate::classify(parts[layout.license_number]);
+        let make = parts[layout.make].trim();
+        let model = parts[layout.model].trim();
+        let optional = layout.optional_parts;
         Ok(VehicleLicenseData {
-            make: format!("{} {}", parts[9], parts[10]),
-            description: parts[8].to_string(),
-            color: parts[11].to_string(),
-            license_number: parts[6].to_string(),
-            vin_number: parts[12].to_string(),
-            vehicle_register_number: parts[7].to_string(),
-            engine_number: parts[13].to_string(),
-            expiry_date: parts[14].to_string(),
+            disc_type: parts[layout.disc_type].to_string(),
+            authority_code: parts[layout.authority_code].to_string(),
+            control_number: parts[layout.control_number].to_string(),
+            disc_number: parts[layout.disc_number].to_string(),
+            make: make.to_string(),
+            model: model.to_string(),
+            make_model: [make, model].iter().filter(|s| !s.is_empty()).copied().collect::<Vec<_>>().join(" "),
+            description: parts[layout.description].to_string(),
+            is_trailer,
+            color: parts[layout.color].to_string(),
+            license_number: parts[layout.license_number].to_string(),
+            license_number_valid: plate.valid,
+            province: plate.province.map(str::to_string),
+            registration_authority: plate.registration_authority.map(str::to_string),
+            vin_number: parts[layout.vin_number].to_string(),
+            vehicle_register_number: parts[layout.vehicle_register_number].to_string(),
+            engine_number: parts[layout.engine_number].to_string(),
+            expiry_date: parts[layout.expiry_date].to_string().into(),
+            gvm: parts.get(optional).and_then(|part| parse_mass(part)),
+            gvm_raw: optional_part(parts, optional),
+            tare: parts.get(optional + 1).and_then(|part| parse_mass(part)),
+            tare_raw: optional_part(parts, optional + 1),
+            persons_seated: parts.get(optional + 2).and_then(|part| part.trim().parse().ok()),
+            persons_standing: parts.get(optional + 3).and_then(|part| part.trim().parse().ok()),
+            vehicle_category: optional_part(parts, optional + 4),
+            warnings: Vec::new(),
+            payload_sha256: None,
         })
     }
+
+    /// The disc expiry date, when it parses to a real calendar date.
+    pub fn expiry(&self) -> Option<LicenseDate> {
+        self.expiry_date.as_date().filter(LicenseDate::is_plausible)
+    }
+
+    /// A disc remains valid through its expiry date. None when the expiry date can't be read.
+    pub fn is_expired(&self, today: LicenseDate) -> Option<bool> {
+        self.expiry().map(|expiry| expiry < today)
+    }
+
+    /// Negative once the disc has expired. None when the expiry date can't be read.
+    pub fn days_until_expiry(&self, today: LicenseDate) -> Option<i64> {
+        self.expiry().map(|expiry| today.days_until(expiry))
+    }
+
+    /// Whether the disc was still current on `date`. Discs carry no issue date, so only expiry is checked.
+    pub fn is_valid_on(&self, date: LicenseDate) -> Option<bool> {
+        self.is_expired(date).map(|expired| !expired)
+    }
+
+    fn text_fields(&self) -> [(&'static str, &str); 13] {
+        [
+            ("disc_type", &self.disc_type),
+            ("authority_code", &self.authority_code),
+            ("control_number", &self.control_number),
+            ("disc_number", &self.disc_number),
+            ("make", &self.make),
+            ("model", &self.model),
+            ("description", &self.description),
+            ("color", &self.color),
+            ("license_number", &self.license_number),
+            ("vin_number", &self.vin_number),
+            ("vehicle_register_number", &self.vehicle_register_number),
+            ("engine_number", &self.engine_number),
+            ("expiry_date", self.expiry_date.as_text().unwrap_or_default()),
+        ]
+    }
 }
 
-#[allow(dead_code)]
-pub fn parse_bytes(bytes: Vec<u8>) -> Result<VehicleLicenseData, ParseError> {
-    let data = String::from_utf8(bytes)?;
-    parse_string(data)
+/// Whether there are enough parts for the standard layout.
+pub(crate) fn matches_layout(parts: &[&str]) -> bool {
+    let is_trailer = parts.get(LAYOUT.description).is_some_and(|description| is_trailer_description(description));
+    parts.len() >= LAYOUT.min_parts(is_trailer)
 }
 
-#[allow(dead_code)]
-pub fn parse_string(data: String) -> Result<VehicleLicenseData, ParseError> {
-    let parts: Vec<&str> = data.split('%').collect();
+/// Whether raw bytes are structured like a disc: a leading '%', or enough '%'-separated parts for the layout.
+/// Invalid UTF-8 doesn't count against it, so a disc with a corrupted byte is still recognised.
+pub(crate) fn looks_like_disc(bytes: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(bytes);
+    text.starts_with('%') || matches_layout(&text.split('%').collect::<Vec<_>>())
+}
+
+// Matches descriptions such as "Trailer", "Semi-trailer" and "Caravan", which is towed like a trailer.
+fn is_trailer_description(description: &str) -> bool {
+    let description = description.to_ascii_lowercase();
+    description.contains("trailer") || description.contains("caravan")
+}
+
+// Heavier-vehicle discs append the GVM and tare after the expiry date, e.g. "3500 kg" or "3500",
+// followed on passenger-carrying vehicles by the persons seated, persons standing and vehicle category.
+fn parse_mass(part: &str) -> Option<u32> {
+    let value: String = part.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_lowercase();
+    // Only a "kg" suffix is dropped, so a misread digit such as "35OO" isn't taken as 35.
+    value.strip_suffix("kg").unwrap_or(&value).parse().ok()
+}
+
+fn optional_part(parts: &[&str], index: usize) -> Option<String> {
+    parts.get(index).filter(|part| !part.trim().is_empty()).map(|part| part.to_string())
+}
+
+/// Rebuilds the `%`-delimited disc string that `parse_string` reads, e.g. for round-trip tests or demo barcodes.
+/// Fields are written as they are, so a `%` inside one would split it when parsed back.
+pub fn encode(data: &VehicleLicenseData) -> String {
+    // Part 4 isn't kept when parsing, so a placeholder digit is written in its place.
+    let mut parts = vec![
+        "",
+        &data.disc_type,
+        &data.authority_code,
+        &data.control_number,
+        "1",
+        &data.disc_number,
+        &data.license_number,
+        &data.vehicle_register_number,
+        &data.description,
+        &data.make,
+        &data.model,
+        &data.color,
+        &data.vin_number,
+        &data.engine_number,
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+    parts.push(data.expiry_date.to_string());
+
+    let optional_parts = [
+        data.gvm_raw.clone().or_else(|| data.gvm.map(|gvm| gvm.to_string())),
+        data.tare_raw.clone().or_else(|| data.tare.map(|tare| tare.to_string())),
+        data.persons_seated.map(|seated| seated.to_string()),
+        data.persons_standing.map(|standing| standing.to_string()),
+        data.vehicle_category.clone(),
+    ];
+    // Only write the optional parts up to the last one present, as light-vehicle discs do.
+    let present = optional_parts.iter().rposition(Option::is_some).map_or(0, |last| last + 1);
+    parts.extend(optional_parts.into_iter().take(present).map(Option::unwrap_or_default));
+
+    // The trailing '%' leaves an empty final part, which also satisfies the minimum part count.
+    parts.push(String::new());
+    parts.join("%")
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<VehicleLicenseData, DecodeError> {
+    parse_bytes_with_options(bytes, ParseOptions::default())
+}
+
+pub fn parse_bytes_with_options(bytes: &[u8], options: ParseOptions) -> Result<VehicleLicenseData, DecodeError> {
+    let mut license = if options.lossy_utf8 {
+        let mut license = parse_string(&String::from_utf8_lossy(bytes))?;
+        license.warnings = license
+            .text_fields()
+            .iter()
+            .filter(|(_, value)| value.contains(char::REPLACEMENT_CHARACTER))
+            .map(|(name, _)| Warning::for_field("INVALID_UTF8", name, format!("Field {} contains invalid UTF-8 bytes", name)))
+            .collect();
+        license
+    } else {
+        parse_string(std::str::from_utf8(bytes)?)?
+    };
+
+    date::apply_format("expiry_date", &mut license.expiry_date, options.date_format, &mut license.warnings);
+    if options.fingerprint {
+        license.payload_sha256 = Some(output::payload_sha256(bytes));
+    }
+
+    Ok(license)
+}
+
+pub fn parse_string_with_options(data: &str, options: ParseOptions) -> Result<VehicleLicenseData, DecodeError> {
+    let mut license = parse_string(data)?;
+    date::apply_format("expiry_date", &mut license.expiry_date, options.date_format, &mut license.warnings);
+    if options.fingerprint {
+        license.payload_sha256 = Some(output::payload_sha256(data.as_bytes()));
+    }
+    Ok(license)
+}
+
+pub fn parse_string(data: &str) -> Result<VehicleLicenseData, DecodeError> {
+    let mut reader = Reader::new(data.as_bytes());
+    let mut parts = Vec::new();
+    loop {
+        let (part, delimiter) = reader.read_until_delimiter_or_end(b"%");
+        // Splitting on an ASCII delimiter keeps every part valid UTF-8.
+        parts.push(std::str::from_utf8(part)?);
+        if delimiter.is_none() {
+            break;
+        }
+    }
     VehicleLicenseData::from_parts(&parts)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISC: &str = "%MVL1CC14%0154%4025T0JB%1%40250031T0JB%CY123456%ABC123GP%Hatch back / Luikrug%VOLKSWAGEN%POLO%White / Wit%AAVZZZ6RZCU012345%CJZ123456%2025-07-31%";
+
+    fn with_masses(gvm: &str, tare: &str) -> VehicleLicenseData {
+        parse_string(&format!("{}{}%{}%", DISC, gvm, tare)).expect("the disc parses")
+    }
+
+    #[test]
+    fn parse_mass_reads_plain_and_unit_suffixed_values() {
+        assert_eq!(parse_mass("3500 kg"), Some(3500));
+        assert_eq!(parse_mass("3500kg"), Some(3500));
+        assert_eq!(parse_mass("3500 KG"), Some(3500));
+        assert_eq!(parse_mass("3500"), Some(3500));
+        assert_eq!(parse_mass(" 3500 "), Some(3500));
+    }
+
+    #[test]
+    fn parse_mass_rejects_empty_and_non_numeric_values() {
+        assert_eq!(parse_mass(""), None);
+        assert_eq!(parse_mass("  "), None);
+        assert_eq!(parse_mass("kg"), None);
+        assert_eq!(parse_mass("unknown"), None);
+        assert_eq!(parse_mass("35OO"), None);
+    }
+
+    #[test]
+    fn masses_keep_the_raw_string() {
+        let license = with_masses("3500 kg", "1200");
+        assert_eq!((license.gvm, license.gvm_raw.as_deref()), (Some(3500), Some("3500 kg")));
+        assert_eq!((license.tare, license.tare_raw.as_deref()), (Some(1200), Some("1200")));
+
+        let license = with_masses("unknown", "");
+        assert_eq!((license.gvm, license.gvm_raw.as_deref()), (None, Some("unknown")));
+        assert_eq!((license.tare, license.tare_raw), (None, None));
+    }
+
+    #[test]
+    fn light_vehicle_discs_have_no_masses() {
+        let license = parse_string(DISC).expect("the disc parses");
+        assert_eq!((license.gvm, license.gvm_raw, license.tare, license.tare_raw), (None, None, None, None));
+    }
+
+    fn with_make_model(make: &str, model: &str) -> VehicleLicenseData {
+        parse_string(&DISC.replace("%VOLKSWAGEN%POLO%", &format!("%{}%{}%", make, model))).expect("the disc parses")
+    }
+
+    #[test]
+    fn make_and_model_are_kept_apart() {
+        let license = parse_string(DISC).expect("the disc parses");
+        assert_eq!((license.make.as_str(), license.model.as_str(), license.make_model.as_str()), ("VOLKSWAGEN", "POLO", "VOLKSWAGEN POLO"));
+    }
+
+    #[test]
+    fn multi_word_makes_stay_whole() {
+        for (make, model) in [("MERCEDES-BENZ", "C 200"), ("LAND ROVER", "DEFENDER 110"), ("ALFA ROMEO", "GIULIA")] {
+            let license = with_make_model(make, model);
+            assert_eq!((license.make.as_str(), license.model.as_str()), (make, model));
+            assert_eq!(license.make_model, format!("{} {}", make, model));
+        }
+    }
+
+    #[test]
+    fn make_and_model_are_trimmed_and_may_be_empty() {
+        let license = with_make_model(" LAND ROVER ", "  ");
+        assert_eq!((license.make.as_str(), license.model.as_str(), license.make_model.as_str()), ("LAND ROVER", "", "LAND ROVER"));
+
+        let license = with_make_model("", "POLO");
+        assert_eq!((license.make.as_str(), license.model.as_str(), license.make_model.as_str()), ("", "POLO", "POLO"));
+
+        let license = with_make_model("", "");
+        assert_eq!((license.make.as_str(), license.model.as_str(), license.make_model.as_str()), ("", "", ""));
+    }
+
+    fn assert_round_trips(disc: &str) -> VehicleLicenseData {
+        let data = parse_string(disc).expect("the disc parses");
+        assert_eq!(parse_string(&encode(&data)).expect("the encoded disc parses"), data);
+        data
+    }
+
+    #[test]
+    fn encode_round_trips_a_light_vehicle_disc() {
+        let data = assert_round_trips(DISC);
+        assert_eq!(encode(&data), DISC);
+    }
+
+    #[test]
+    fn encode_round_trips_a_trailer_without_an_engine_number() {
+        let disc = "%MVL1CC14%0154%4025T0JB%1%40250031T0JB%CY654321%XYZ789GP%Trailer / Sleepwa%HENRED%FLATDECK%Grey / Grys%ADV12345678901234%%2025-07-31%750 kg%350%";
+        let data = assert_round_trips(disc);
+        assert!(data.is_trailer);
+        assert_eq!(data.engine_number, "");
+        assert_eq!((data.gvm, data.tare), (Some(750), Some(350)));
+    }
+
+    #[test]
+    fn encode_round_trips_masses_with_and_without_the_unit() {
+        for (gvm, tare) in [("3500 kg", "1200"), ("3500", "1200kg"), ("3500 KG", "1200 kg")] {
+            let data = assert_round_trips(&format!("{}{}%{}%", DISC, gvm, tare));
+            assert_eq!((data.gvm_raw.as_deref(), data.tare_raw.as_deref()), (Some(gvm), Some(tare)));
+        }
+        let data = assert_round_trips(&format!("{}16000 kg%9000%60%20%BUS%", DISC));
+        assert_eq!((data.persons_seated, data.persons_standing, data.vehicle_category.as_deref()), (Some(60), Some(20), Some("BUS")));
+    }
+
+    #[test]
+    fn lossy_mode_flags_the_field_with_invalid_utf8() {
+        let mut bytes = DISC.as_bytes().to_vec();
+        let make = DISC.find("VOLKSWAGEN").expect("the disc has a make");
+        bytes[make + 4] = 0xff;
+
+        assert!(matches!(parse_bytes(&bytes), Err(DecodeError::InvalidUtf8(_))));
+
+        let license = parse_bytes_with_options(&bytes, ParseOptions { lossy_utf8: true, ..ParseOptions::default() }).expect("lossy mode parses");
+        assert_eq!(license.make, "VOLK\u{fffd}WAGEN");
+        let expected = parse_string(DISC).expect("the disc parses");
+        assert_eq!(VehicleLicenseData { make: expected.make.clone(), make_model: expected.make_model.clone(), warnings: Vec::new(), ..license.clone() }, expected);
+
+        let fields: Vec<_> = license.warnings.iter().map(|warning| (warning.code.as_str(), warning.field.as_deref())).collect();
+        assert_eq!(fields, [("INVALID_UTF8", Some("make"))]);
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Something worth surfacing that didn't fail the parse, e.g. an implausible date or an unknown code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct Warning {
+    /// A stable code to branch on, e.g. "IMPLAUSIBLE_DATE".
+    pub code: String,
+    pub message: String,
+    /// The output field concerned, when there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl Warning {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Warning { code: code.to_string(), message: message.into(), field: None }
+    }
+
+    pub fn for_field(code: &str, field: &str, message: impl Into<String>) -> Self {
+        Warning { field: Some(field.to_string()), ..Warning::new(code, message) }
+    }
+
+    // Ages at issue, and an implausible birthdate echoed back, would undo redacting the birthdate.
+    pub(crate) fn reveals_birthdate(&self) -> bool {
+        self.code == "ISSUE_AGE" || (self.code == "IMPLAUSIBLE_DATE" && self.field.as_deref() == Some("birthdate"))
+    }
+}
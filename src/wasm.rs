@@ -0,0 +1,842 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use wasm_bindgen::prelude::*;
+use crate::accumulator::{AccumulatedScan, ScanAccumulator};
+use crate::date::LicenseDate;
+use crate::drivers_license::{self, AgeVerification, DriversLicenseData, ParseOptions as DriversParseOptions, VersionKeys};
+use crate::error::DecodeError;
+use crate::fields;
+use crate::input;
+use crate::license_code;
+use crate::validation::ValidationReport;
+use crate::license::{self, License, LicenseFormat, ParseOptions as LicenseParseOptions};
+use crate::output;
+use crate::segments::{MacroSegment, SegmentAssembler};
+use crate::smart_id::{self, SmartIdData, ParseOptions as SmartIdParseOptions};
+use crate::vehicle_license::{self, VehicleLicenseData, ParseOptions as VehicleParseOptions};
+use crate::wedge::KeyboardLayout;
+
+#[wasm_bindgen(typescript_custom_section)]
+const LICENSE_DECODE_ERROR: &'static str = r#"
+export interface LicenseDecodeError extends Error {
+    name: "LicenseDecodeError";
+    code:
+        | "INSUFFICIENT_BYTES"
+        | "UNKNOWN_VERSION"
+        | "UNKNOWN_KEY"
+        | "INVALID_KEY"
+        | "DECRYPT_FAILED"
+        | "TRUNCATED_DATA"
+        | "MISSING_IMAGE"
+        | "INVALID_UTF8"
+        | "INVALID_BASE64"
+        | "INVALID_HEX"
+        | "INSUFFICIENT_PARTS"
+        | "UNRECOGNIZED_FORMAT"
+        | "INVALID_OPTIONS"
+        | "SERIALIZATION_FAILED"
+        | "ENCODE_FAILED"
+        | "INCOMPLETE_SEGMENTS"
+        | "SEGMENT_MISMATCH";
+    // Where in a driver's licence decoding stopped: the section and field being read, e.g. "birthdate", the byte
+    // offset into the decrypted payload and the RSA block (1-6) that byte was decrypted from.
+    section?: "header" | "strings" | "nibbles" | "image_header";
+    field?: string;
+    offset?: number;
+    rsa_block?: number;
+}
+
+export type ErrorDetails = Pick<LicenseDecodeError, "code" | "message" | "section" | "field" | "offset" | "rsa_block">;
+
+export type AnyDocument =
+    | { format: string; data: DriversLicenseData | VehicleLicenseData | SmartIdData }
+    | { format: string; error: ErrorDetails };
+
+export type BatchResult<T> =
+    | { ok: true; data: T }
+    | { ok: false; error: ErrorDetails };
+
+// The declared return types describe the default snake_case output. With `camel_case: true` the keys are renamed, so
+// type the result as e.g. `CamelCased<DriversLicenseData>` instead.
+type CamelCaseKey<K extends string> = K extends `${infer Head}_${infer Tail}` ? `${Head}${Capitalize<CamelCaseKey<Tail>>}` : K;
+export type CamelCased<T> = T extends Uint8Array
+    ? T
+    : T extends (infer Item)[]
+      ? CamelCased<Item>[]
+      : T extends object
+        ? { [K in keyof T as K extends string ? CamelCaseKey<K> : K]: CamelCased<T[K]> }
+        : T;
+"#;
+
+// Typed handles for the optional options argument, so it can be omitted from JS.
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "LicenseParseOptions")]
+    pub type LicenseOptionsArg;
+    #[wasm_bindgen(typescript_type = "DriversParseOptions")]
+    pub type DriversOptionsArg;
+    #[wasm_bindgen(typescript_type = "VehicleParseOptions")]
+    pub type VehicleOptionsArg;
+    #[wasm_bindgen(typescript_type = "SmartIdParseOptions")]
+    pub type SmartIdOptionsArg;
+}
+
+#[derive(Serialize)]
+struct BatchResult<T> {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<BatchError>,
+}
+
+#[derive(Serialize)]
+struct BatchError {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rsa_block: Option<usize>,
+}
+
+impl From<&DecodeError> for BatchError {
+    fn from(error: &DecodeError) -> Self {
+        BatchError {
+            code: error.code(),
+            message: error.to_string(),
+            section: error.section().map(|section| section.id()),
+            field: error.field(),
+            offset: error.offset(),
+            rsa_block: error.rsa_block(),
+        }
+    }
+}
+
+impl<T> From<Result<T, DecodeError>> for BatchResult<T> {
+    fn from(result: Result<T, DecodeError>) -> Self {
+        match result {
+            Ok(data) => BatchResult { ok: true, data: Some(data), error: None },
+            Err(e) => BatchResult { ok: false, data: None, error: Some(BatchError::from(&e)) },
+        }
+    }
+}
+
+/// "drivers", "vehicle" or "smart_id", or undefined when the payload is none of them. Nothing is decrypted.
+#[wasm_bindgen(unchecked_return_type = "LicenseFormat | undefined")]
+pub fn detect_format(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let format: Option<LicenseFormat> = crate::detect_format(bytes);
+    to_js_value(&format)
+}
+
+/// The payload inside base64, a `data:` URI, an AIM identifier (e.g. "]L2"), byte order marks or whitespace.
+/// `parse_license` and `detect_format` already do this.
+#[wasm_bindgen]
+pub fn normalize_input(bytes: &[u8]) -> Vec<u8> {
+    crate::normalize_input(bytes)
+}
+
+/// The formats the payload might be as `[{ format, confidence, reason }]`, most likely first. Nothing is decrypted.
+#[wasm_bindgen(unchecked_return_type = "FormatCandidate[]")]
+pub fn sniff(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    to_js_value(&crate::sniff(bytes))
+}
+
+/// The driver's licence version (1 or 2) read from the header, or undefined for an unknown header.
+#[wasm_bindgen]
+pub fn get_license_version(bytes: &[u8]) -> Option<u8> {
+    crate::get_license_version(bytes)
+}
+
+#[wasm_bindgen(unchecked_return_type = "License")]
+pub fn parse_license(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    let (drivers_camel_case, vehicle_camel_case, smart_id_camel_case) =
+        (options.drivers.camel_case, options.vehicle.camel_case, options.smart_id.camel_case);
+    license::parse_bytes_with_options(bytes, options).map_err(|e| to_js_error(&e)).and_then(|license: License| {
+        let camel_case = match license {
+            License::Drivers(_) => drivers_camel_case,
+            License::Vehicle(_) => vehicle_camel_case,
+            License::SmartId(_) => smart_id_camel_case,
+        };
+        to_js_value_with_case(&license, camel_case)
+    })
+}
+
+/// The parsed document as canonical JSON, for hashing or signing: keys sorted, no whitespace, and no null or empty
+/// values. Keys are always snake_case, whatever `camel_case` is set to.
+#[wasm_bindgen]
+pub fn parse_license_canonical(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<String, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    license::parse_bytes_with_options(bytes, options)
+        .and_then(|license: License| output::to_canonical_json(&license))
+        .map_err(|e| to_js_error(&e))
+}
+
+/// `{ schemaVersion, format, data }`, e.g. `{ schemaVersion: 1, format: "sa_drivers_v2", data }`. `schemaVersion`
+/// changes whenever a field is renamed, removed or changes type.
+#[wasm_bindgen(unchecked_return_type = "VersionedDocument")]
+pub fn parse_versioned(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    let (drivers_camel_case, vehicle_camel_case, smart_id_camel_case) =
+        (options.drivers.camel_case, options.vehicle.camel_case, options.smart_id.camel_case);
+    license::parse_versioned(bytes, options).map_err(|e| to_js_error(&e)).and_then(|document| {
+        let camel_case = match document.data {
+            License::Drivers(_) => drivers_camel_case,
+            License::Vehicle(_) => vehicle_camel_case,
+            License::SmartId(_) => smart_id_camel_case,
+        };
+        to_js_value_with_case(&document, camel_case)
+    })
+}
+
+/// Name, type, description, sensitivity and example for each output field of `format`: "drivers", "vehicle",
+/// "smart_id" or a format id such as "sa_drivers_v2".
+#[wasm_bindgen(unchecked_return_type = "FieldDescription[]")]
+pub fn describe_fields(format: &str) -> Result<JsValue, JsValue> {
+    let format = license::format_from_name(format).ok_or_else(|| to_js_error(&DecodeError::UnrecognizedFormat))?;
+    to_js_value(&fields::describe_fields(format))
+}
+
+/// A JSON Schema for the output of `format`: "drivers", "vehicle", "smart_id" or a format id such as "sa_drivers_v2".
+#[cfg(feature = "schema")]
+#[wasm_bindgen]
+pub fn get_schema(format: &str) -> Result<JsValue, JsValue> {
+    let format = license::format_from_name(format).ok_or_else(|| to_js_error(&DecodeError::UnrecognizedFormat))?;
+    to_js_json(&crate::schema::schema(format))
+}
+
+/// The parsed document as XML, see `xml::to_xml` for the element structure.
+#[cfg(feature = "xml")]
+#[wasm_bindgen]
+pub fn parse_license_xml(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<String, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    license::parse_bytes_with_options(bytes, options)
+        .and_then(|license: License| crate::xml::to_xml(&license))
+        .map_err(|e| to_js_error(&e))
+}
+
+/// `{ data, signature }`: the canonical JSON from `parse_license_canonical` and its hex HMAC-SHA256 under `key`.
+#[wasm_bindgen(unchecked_return_type = "SignedEnvelope")]
+pub fn parse_license_signed(bytes: &[u8], key: &[u8], options: Option<LicenseOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    license::parse_bytes_with_options(bytes, options)
+        .and_then(|license: License| output::sign_canonical(&license, key))
+        .map_err(|e| to_js_error(&e))
+        .and_then(|envelope| to_js_value(&envelope))
+}
+
+/// `{ format, data }` for any registered format, e.g. `{ format: "sa_drivers_v2", data }`. Doesn't throw for bad
+/// payloads: they give `{ format, error }`, with format "unrecognized" when no parser recognises them.
+#[wasm_bindgen(unchecked_return_type = "AnyDocument")]
+pub fn parse_any(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    let (drivers_camel_case, vehicle_camel_case, smart_id_camel_case) =
+        (options.drivers.camel_case, options.vehicle.camel_case, options.smart_id.camel_case);
+    let document = license::parse_any(bytes, options);
+
+    let result = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&result, &JsValue::from_str("format"), &JsValue::from_str(&document.format));
+    let (key, value) = match document.result {
+        Ok(License::Drivers(data)) => ("data", to_js_value_with_case(&data, drivers_camel_case)?),
+        Ok(License::Vehicle(data)) => ("data", to_js_value_with_case(&data, vehicle_camel_case)?),
+        Ok(License::SmartId(data)) => ("data", to_js_value_with_case(&data, smart_id_camel_case)?),
+        Err(e) => ("error", to_js_value(&BatchError::from(&e))?),
+    };
+    let _ = js_sys::Reflect::set(&result, &JsValue::from_str(key), &value);
+    Ok(result.into())
+}
+
+#[wasm_bindgen(unchecked_return_type = "SmartIdData")]
+pub fn parse_smart_id_card(bytes: &[u8], options: Option<SmartIdOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: SmartIdParseOptions = read_options(options)?;
+    smart_id::parse_bytes_with_options(bytes, options)
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: SmartIdData| to_js_value_with_case(&data, options.camel_case))
+}
+
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    drivers_to_js(bytes, read_options(options)?)
+}
+
+/// The parsed licence as a JSON string, skipping the conversion to a JS object.
+#[wasm_bindgen]
+pub fn parse_drivers_license_json(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<String, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    let camel_case = options.camel_case;
+    drivers_license::parse_bytes_with_options(bytes, options)
+        .and_then(|data: DriversLicenseData| output::to_json_string(&data, camel_case))
+        .map_err(|e| to_js_error(&e))
+}
+
+/// The parsed licence as CBOR bytes, for storing or sending results without the JSON overhead.
+#[cfg(feature = "cbor")]
+#[wasm_bindgen]
+pub fn parse_drivers_license_cbor(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<Vec<u8>, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    drivers_license::parse_bytes_with_options(bytes, options)
+        .and_then(|data: DriversLicenseData| output::to_cbor(&data))
+        .map_err(|e| to_js_error(&e))
+}
+
+/// Any payload `parse_license` accepts, as CBOR bytes of its `{ type, data }` result.
+#[cfg(feature = "cbor")]
+#[wasm_bindgen]
+pub fn parse_license_cbor(bytes: &[u8], options: Option<LicenseOptionsArg>) -> Result<Vec<u8>, JsValue> {
+    let options: LicenseParseOptions = read_options(options)?;
+    license::parse_bytes_with_options(bytes, options)
+        .and_then(|license: License| output::to_cbor(&license))
+        .map_err(|e| to_js_error(&e))
+}
+
+/// Recovers the scanned bytes from keyboard-wedge text, with `unrecoverable` positions and `missing_bytes`.
+/// `layout` is the host keyboard layout: "us" (default), "uk" or "german".
+#[wasm_bindgen(unchecked_return_type = "WedgeRepair")]
+pub fn repair_wedge_text(
+    text: &str,
+    #[wasm_bindgen(unchecked_param_type = "KeyboardLayout | undefined")] layout: JsValue,
+) -> Result<JsValue, JsValue> {
+    let layout: KeyboardLayout = if layout.is_undefined() {
+        KeyboardLayout::default()
+    } else {
+        serde_wasm_bindgen::from_value(layout).map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?
+    };
+    to_js_value(&crate::repair_wedge_text(text, layout))
+}
+
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_base64(input: &str, options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    let camel_case = options.camel_case;
+    input::decode_base64(input)
+        .and_then(|bytes| drivers_license::parse_bytes_with_options(&bytes, options))
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: DriversLicenseData| to_js_value_with_case(&data, camel_case))
+}
+
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_hex(input: &str, options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    let camel_case = options.camel_case;
+    input::decode_hex(input)
+        .and_then(|bytes| drivers_license::parse_bytes_with_options(&bytes, options))
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: DriversLicenseData| to_js_value_with_case(&data, camel_case))
+}
+
+/// `parse_drivers_license` with `tolerant_length` set.
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_tolerant(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    drivers_to_js(bytes, DriversParseOptions { tolerant_length: true, ..options })
+}
+
+/// `parse_drivers_license` with `lenient` set.
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_lenient(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    drivers_to_js(bytes, DriversParseOptions { lenient: true, ..options })
+}
+
+/// `parse_drivers_license` with a required options object.
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_with_options(
+    bytes: &[u8],
+    #[wasm_bindgen(unchecked_param_type = "DriversParseOptions")] options: JsValue,
+) -> Result<JsValue, JsValue> {
+    drivers_to_js(bytes, read_options(Some(options))?)
+}
+
+/// `parse_drivers_license` with `keys` taken from its own argument.
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_with_keys(
+    bytes: &[u8],
+    #[wasm_bindgen(unchecked_param_type = "VersionKeys[]")] keys: JsValue,
+    options: Option<DriversOptionsArg>,
+) -> Result<JsValue, JsValue> {
+    let keys: Vec<VersionKeys> = serde_wasm_bindgen::from_value(keys)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    let options: DriversParseOptions = read_options(options)?;
+    drivers_to_js(bytes, DriversParseOptions { keys, ..options })
+}
+
+/// `parse_drivers_license` with `include_image` set.
+#[wasm_bindgen(unchecked_return_type = "DriversLicenseData")]
+pub fn parse_drivers_license_with_image(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: DriversParseOptions = read_options(options)?;
+    drivers_to_js(bytes, DriversParseOptions { include_image: true, ..options })
+}
+
+#[wasm_bindgen(unchecked_return_type = "BatchResult<DriversLicenseData>[]")]
+pub fn parse_drivers_licenses(
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] payloads: js_sys::Array,
+) -> Result<JsValue, JsValue> {
+    let payloads = payloads
+        .iter()
+        .map(|payload| payload.dyn_into::<js_sys::Uint8Array>().map(|payload| payload.to_vec()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| to_js_error(&DecodeError::InvalidOptions("payloads must be an array of Uint8Array".to_string())))?;
+    let results: Vec<BatchResult<DriversLicenseData>> =
+        crate::parse_drivers_licenses(payloads.iter().map(Vec::as_slice)).into_iter().map(BatchResult::from).collect();
+    to_js_value(&results)
+}
+
+/// A parsed driver's licence with typed getters and helper methods, as an alternative to the plain object.
+#[wasm_bindgen]
+pub struct DriversLicense {
+    data: DriversLicenseData,
+}
+
+#[wasm_bindgen]
+impl DriversLicense {
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8], options: Option<DriversOptionsArg>) -> Result<DriversLicense, JsValue> {
+        let options: DriversParseOptions = read_options(options)?;
+        drivers_license::parse_bytes_with_options(bytes, options)
+            .map(|data| DriversLicense { data })
+            .map_err(|e| to_js_error(&e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn surname(&self) -> String {
+        self.data.surname.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn initials(&self) -> String {
+        self.data.initials.clone()
+    }
+
+    #[wasm_bindgen(getter = licenseNumber)]
+    pub fn license_number(&self) -> String {
+        self.data.license_number.clone()
+    }
+
+    #[wasm_bindgen(getter = idNumber)]
+    pub fn id_number(&self) -> String {
+        self.data.id_number.clone()
+    }
+
+    #[wasm_bindgen(getter = idCountryOfIssue)]
+    pub fn id_country_of_issue(&self) -> String {
+        self.data.id_country_of_issue.code().to_string()
+    }
+
+    #[wasm_bindgen(getter = vehicleCodes)]
+    pub fn vehicle_codes(&self) -> Vec<String> {
+        self.data.vehicle_codes.clone()
+    }
+
+    #[wasm_bindgen(getter = prDpCode)]
+    pub fn pr_dp_code(&self) -> Option<String> {
+        self.data.pr_dp_code.clone()
+    }
+
+    /// "male", "female" or "unknown".
+    #[wasm_bindgen(getter)]
+    pub fn gender(&self) -> String {
+        self.data.gender.gender().as_str().to_string()
+    }
+
+    /// Dates are in the `date_format` the licence was parsed with, "YYYY/MM/DD" by default.
+    #[wasm_bindgen(getter)]
+    pub fn birthdate(&self) -> String {
+        self.data.birthdate.to_string()
+    }
+
+    #[wasm_bindgen(getter = licenseIssueDate)]
+    pub fn license_issue_date(&self) -> String {
+        self.data.license_issue_date.to_string()
+    }
+
+    #[wasm_bindgen(getter = licenseExpiryDate)]
+    pub fn license_expiry_date(&self) -> String {
+        self.data.license_expiry_date.to_string()
+    }
+
+    /// `{ code, message, field? }` for each condition that didn't fail the parse.
+    #[wasm_bindgen(getter, unchecked_return_type = "Warning[]")]
+    pub fn warnings(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&self.data.warnings)
+    }
+
+    /// Initials and surname, e.g. "J SMITH".
+    #[wasm_bindgen(js_name = fullName)]
+    pub fn full_name(&self) -> String {
+        self.data.full_name()
+    }
+
+    /// Whether the licence has expired on `today` (default: the local date). Undefined when the expiry date can't be read.
+    #[wasm_bindgen(js_name = isExpired)]
+    pub fn is_expired(&self, today: Option<String>) -> Option<bool> {
+        self.data.is_expired(reference_date(today)?)
+    }
+
+    /// Whether the holder has licence code `code` itself, e.g. "EB".
+    #[wasm_bindgen(js_name = hasCode)]
+    pub fn has_code(&self, code: &str) -> bool {
+        self.data.has_code(code)
+    }
+
+    /// Whether the held codes authorise driving code `code`, including codes they imply.
+    #[wasm_bindgen(js_name = allowsDriving)]
+    pub fn allows_driving(&self, code: &str) -> bool {
+        license_code::allows_driving(&self.data, code)
+    }
+
+    /// The same plain object `parse_drivers_license` returns. Also used by `JSON.stringify`.
+    #[wasm_bindgen(js_name = toJSON, unchecked_return_type = "DriversLicenseData")]
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&self.data)
+    }
+}
+
+/// Collects repeated scans of one damaged card and merges them into a best-effort parse. Call `free()` when done with it.
+#[wasm_bindgen(js_name = ScanAccumulator)]
+pub struct JsScanAccumulator {
+    accumulator: ScanAccumulator,
+    camel_case: bool,
+}
+
+#[wasm_bindgen(js_class = ScanAccumulator)]
+impl JsScanAccumulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: Option<DriversOptionsArg>) -> Result<JsScanAccumulator, JsValue> {
+        let options: DriversParseOptions = read_options(options)?;
+        let camel_case = options.camel_case;
+        Ok(JsScanAccumulator { accumulator: ScanAccumulator::with_options(options), camel_case })
+    }
+
+    pub fn add(&mut self, bytes: &[u8]) {
+        self.accumulator.add(bytes);
+    }
+
+    /// The number of scans added so far.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.accumulator.len()
+    }
+
+    /// `{ data, confidence, scans }`, where `confidence` is the average share of scans agreeing with each field.
+    #[wasm_bindgen(unchecked_return_type = "AccumulatedScan")]
+    pub fn result(&self) -> Result<JsValue, JsValue> {
+        self.accumulator
+            .result()
+            .map_err(|e| to_js_error(&e))
+            .and_then(|result: AccumulatedScan| to_js_value_with_case(&result, self.camel_case))
+    }
+}
+
+/// Joins Macro PDF417 segments that arrive in any order. Call `free()` when done with it.
+#[wasm_bindgen(js_name = SegmentAssembler)]
+pub struct JsSegmentAssembler {
+    assembler: SegmentAssembler,
+}
+
+#[wasm_bindgen(js_class = SegmentAssembler)]
+impl JsSegmentAssembler {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsSegmentAssembler {
+        JsSegmentAssembler { assembler: SegmentAssembler::new() }
+    }
+
+    pub fn add(&mut self, #[wasm_bindgen(unchecked_param_type = "MacroSegment")] segment: JsValue) -> Result<(), JsValue> {
+        let segment: MacroSegment = serde_wasm_bindgen::from_value(segment)
+            .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+        self.assembler.add(segment).map_err(|e| to_js_error(&e))
+    }
+
+    /// Segment indices still to be scanned.
+    #[wasm_bindgen(getter)]
+    pub fn missing(&self) -> Vec<u32> {
+        self.assembler.missing()
+    }
+
+    #[wasm_bindgen(getter = isComplete)]
+    pub fn is_complete(&self) -> bool {
+        self.assembler.is_complete()
+    }
+
+    /// The joined payload. Throws `INCOMPLETE_SEGMENTS` until every segment has been added.
+    pub fn assemble(&self) -> Result<Vec<u8>, JsValue> {
+        self.assembler.assemble().map_err(|e| to_js_error(&e))
+    }
+}
+
+impl Default for JsSegmentAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether any of `vehicle_codes`, e.g. a parsed licence's `vehicle_codes`, authorises driving code `code`.
+#[wasm_bindgen]
+pub fn allows_driving(vehicle_codes: Vec<String>, code: &str) -> bool {
+    vehicle_codes.iter().any(|held| license_code::covers(held, code))
+}
+
+#[wasm_bindgen(unchecked_return_type = "ValidationReport")]
+pub fn validate_drivers_license(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    crate::validate_drivers_license(bytes)
+        .map_err(|e| to_js_error(&e))
+        .and_then(|report: ValidationReport| to_js_value(&report))
+}
+
+/// Only the outcome leaves the module: the birthdate and ID number are never returned to JS.
+#[wasm_bindgen(unchecked_return_type = "AgeVerification")]
+pub fn is_older_than(bytes: &[u8], years: u16) -> Result<JsValue, JsValue> {
+    crate::is_older_than(bytes, years, today())
+        .map_err(|e| to_js_error(&e))
+        .and_then(|verification: AgeVerification| to_js_value(&verification))
+}
+
+#[wasm_bindgen]
+pub fn decrypt_drivers_license(bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    crate::decrypt_drivers_license(bytes).map_err(|e| to_js_error(&e))
+}
+
+#[wasm_bindgen]
+pub fn extract_drivers_license_image(bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    drivers_license::extract_image(bytes).map_err(|e| to_js_error(&e))
+}
+
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license(bytes: &[u8], options: Option<VehicleOptionsArg>) -> Result<JsValue, JsValue> {
+    vehicle_to_js(bytes, read_options(options)?)
+}
+
+/// The parsed disc as a JSON string, skipping the conversion to a JS object.
+#[wasm_bindgen]
+pub fn parse_vehicle_license_json(bytes: &[u8], options: Option<VehicleOptionsArg>) -> Result<String, JsValue> {
+    let options: VehicleParseOptions = read_options(options)?;
+    vehicle_license::parse_bytes_with_options(bytes, options)
+        .and_then(|data: VehicleLicenseData| output::to_json_string(&data, options.camel_case))
+        .map_err(|e| to_js_error(&e))
+}
+
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license_string(input: &str, options: Option<VehicleOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: VehicleParseOptions = read_options(options)?;
+    vehicle_license::parse_string_with_options(input, options)
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: VehicleLicenseData| to_js_value_with_case(&data, options.camel_case))
+}
+
+/// CSV for parsed documents from `parse_license`: a header line and one row per document, for spreadsheet exports.
+/// Columns follow `describe_fields` order, with "type" first.
+#[wasm_bindgen]
+pub fn to_csv(#[wasm_bindgen(unchecked_param_type = "License | License[]")] licenses: JsValue) -> Result<String, JsValue> {
+    let licenses: Vec<License> = if licenses.is_array() {
+        serde_wasm_bindgen::from_value(licenses)
+    } else {
+        serde_wasm_bindgen::from_value(licenses).map(|license| vec![license])
+    }
+    .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    Ok(crate::csv::to_csv(&licenses))
+}
+
+/// A vCard 4.0 for a parsed licence's holder, with the licence number and codes in `X-SA-LICENSE-NUMBER` and
+/// `X-SA-LICENSE-CODES`.
+#[wasm_bindgen]
+pub fn to_vcard(#[wasm_bindgen(unchecked_param_type = "DriversLicenseData")] data: JsValue) -> Result<String, JsValue> {
+    let data: DriversLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    Ok(crate::vcard::to_vcard(&data))
+}
+
+/// A parsed licence as ISO 18013-5 mDL data elements: `{ docType, nameSpaces: { "org.iso.18013.5.1": { ... } } }`.
+#[wasm_bindgen(unchecked_return_type = "MdlDocument")]
+pub fn to_mdl(#[wasm_bindgen(unchecked_param_type = "DriversLicenseData")] data: JsValue) -> Result<JsValue, JsValue> {
+    let data: DriversLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    to_js_value(&crate::mdl::to_mdl(&data))
+}
+
+/// A parsed licence as an unsigned W3C Verifiable Credential for an issuer service to sign.
+#[cfg(feature = "vc")]
+#[wasm_bindgen(unchecked_return_type = "UnsignedCredential")]
+pub fn to_credential(#[wasm_bindgen(unchecked_param_type = "DriversLicenseData")] data: JsValue) -> Result<JsValue, JsValue> {
+    let data: DriversLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    to_js_value(&crate::vc::to_credential(&data))
+}
+
+/// A parsed licence as AAMVA DL/ID data elements keyed by element ID, e.g. `{ DCS: "SMITH", DAQ: "...", DBB: "01011985" }`.
+#[wasm_bindgen(unchecked_return_type = "AamvaElements")]
+pub fn to_aamva(#[wasm_bindgen(unchecked_param_type = "DriversLicenseData")] data: JsValue) -> Result<JsValue, JsValue> {
+    let data: DriversLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    to_js_value(&crate::aamva::to_aamva(&data))
+}
+
+/// The body of an AAMVA "DL" subfile for a parsed licence, for software that parses raw AAMVA scanner output.
+#[wasm_bindgen]
+pub fn to_aamva_subfile(#[wasm_bindgen(unchecked_param_type = "DriversLicenseData")] data: JsValue) -> Result<String, JsValue> {
+    let data: DriversLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    Ok(crate::aamva::to_aamva(&data).to_subfile())
+}
+
+/// The `%`-delimited disc string for a parsed disc, e.g. for rendering demo barcodes.
+#[wasm_bindgen]
+pub fn encode_vehicle_license(
+    #[wasm_bindgen(unchecked_param_type = "VehicleLicenseData")] data: JsValue,
+) -> Result<String, JsValue> {
+    let data: VehicleLicenseData = serde_wasm_bindgen::from_value(data)
+        .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string())))?;
+    Ok(crate::encode_vehicle_license(&data))
+}
+
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license_base64(input: &str, options: Option<VehicleOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: VehicleParseOptions = read_options(options)?;
+    input::decode_base64(input)
+        .and_then(|bytes| vehicle_license::parse_bytes_with_options(&bytes, options))
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: VehicleLicenseData| to_js_value_with_case(&data, options.camel_case))
+}
+
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license_hex(input: &str, options: Option<VehicleOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: VehicleParseOptions = read_options(options)?;
+    input::decode_hex(input)
+        .and_then(|bytes| vehicle_license::parse_bytes_with_options(&bytes, options))
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: VehicleLicenseData| to_js_value_with_case(&data, options.camel_case))
+}
+
+/// `parse_vehicle_license` with a required options object.
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license_with_options(
+    bytes: &[u8],
+    #[wasm_bindgen(unchecked_param_type = "VehicleParseOptions")] options: JsValue,
+) -> Result<JsValue, JsValue> {
+    vehicle_to_js(bytes, read_options(Some(options))?)
+}
+
+/// `parse_vehicle_license` with `lossy_utf8` set.
+#[wasm_bindgen(unchecked_return_type = "VehicleLicenseData")]
+pub fn parse_vehicle_license_lossy(bytes: &[u8], options: Option<VehicleOptionsArg>) -> Result<JsValue, JsValue> {
+    let options: VehicleParseOptions = read_options(options)?;
+    vehicle_to_js(bytes, VehicleParseOptions { lossy_utf8: true, ..options })
+}
+
+/// Whether a licence or disc with this expiry date (any supported format) has expired.
+/// `today` defaults to the local date.
+#[wasm_bindgen]
+pub fn is_expired(expiry_date: &str, today: Option<String>) -> Option<bool> {
+    let today = reference_date(today)?;
+    parse_date(expiry_date).map(|expiry| expiry < today)
+}
+
+/// Days from `today` (default: the local date) until this expiry date, negative once it has passed.
+#[wasm_bindgen]
+pub fn days_until_expiry(expiry_date: &str, today: Option<String>) -> Option<i32> {
+    let today = reference_date(today)?;
+    parse_date(expiry_date).map(|expiry| today.days_until(expiry) as i32)
+}
+
+/// Whether the licence or disc in `bytes` was valid on `date` (default: the local date).
+/// Undefined when the relevant dates can't be read.
+#[wasm_bindgen]
+pub fn is_valid_on(bytes: &[u8], date: Option<String>) -> Result<Option<bool>, JsValue> {
+    let license = crate::parse_license(bytes).map_err(|e| to_js_error(&e))?;
+    let date = reference_date(date).ok_or_else(|| to_js_error(&DecodeError::InvalidOptions("unrecognised date".to_string())))?;
+    Ok(license.is_valid_on(date))
+}
+
+fn parse_date(date: &str) -> Option<LicenseDate> {
+    LicenseDate::parse(date).filter(LicenseDate::is_plausible)
+}
+
+fn reference_date(date: Option<String>) -> Option<LicenseDate> {
+    match date {
+        Some(date) => parse_date(&date),
+        None => Some(today()),
+    }
+}
+
+fn today() -> LicenseDate {
+    let now = js_sys::Date::new_0();
+    LicenseDate::new(now.get_full_year() as u16, now.get_month() as u8 + 1, now.get_date() as u8)
+}
+
+// The options path every driver's licence export goes through, so `camel_case` applies to all of them.
+fn drivers_to_js(bytes: &[u8], options: DriversParseOptions) -> Result<JsValue, JsValue> {
+    let camel_case = options.camel_case;
+    drivers_license::parse_bytes_with_options(bytes, options)
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: DriversLicenseData| to_js_value_with_case(&data, camel_case))
+}
+
+fn vehicle_to_js(bytes: &[u8], options: VehicleParseOptions) -> Result<JsValue, JsValue> {
+    vehicle_license::parse_bytes_with_options(bytes, options)
+        .map_err(|e| to_js_error(&e))
+        .and_then(|data: VehicleLicenseData| to_js_value_with_case(&data, options.camel_case))
+}
+
+fn read_options<T: DeserializeOwned + Default>(options: Option<impl Into<JsValue>>) -> Result<T, JsValue> {
+    match options {
+        Some(options) => serde_wasm_bindgen::from_value(options.into())
+            .map_err(|e| to_js_error(&DecodeError::InvalidOptions(e.to_string()))),
+        None => Ok(T::default()),
+    }
+}
+
+fn to_js_value<T: Serialize>(data: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(data).map_err(|e| to_js_error(&DecodeError::SerializationFailed(e.to_string())))
+}
+
+// For `serde_json::Value`s, whose objects would otherwise become JS `Map`s.
+#[cfg(feature = "schema")]
+fn to_js_json<T: Serialize>(data: &T) -> Result<JsValue, JsValue> {
+    data.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| to_js_error(&DecodeError::SerializationFailed(e.to_string())))
+}
+
+// Callers returning camelCase keys are typed as the snake_case type; `CamelCased<T>` in the TS section describes them.
+fn to_js_value_with_case<T: Serialize>(data: &T, camel_case: bool) -> Result<JsValue, JsValue> {
+    let value = to_js_value(data)?;
+    Ok(if camel_case { camel_case_js_keys(value) } else { value })
+}
+
+// Renames keys on the JS side, so byte fields stay Uint8Arrays rather than round-tripping through JSON.
+fn camel_case_js_keys(value: JsValue) -> JsValue {
+    if let Some(array) = value.dyn_ref::<js_sys::Array>() {
+        return array.iter().map(camel_case_js_keys).collect::<js_sys::Array>().into();
+    }
+    if !value.is_object() || value.is_instance_of::<js_sys::Uint8Array>() {
+        return value;
+    }
+    let renamed = js_sys::Object::new();
+    for entry in js_sys::Object::entries(value.unchecked_ref()).iter() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let key = entry.get(0).as_string().unwrap_or_default();
+        let _ = js_sys::Reflect::set(&renamed, &JsValue::from_str(&output::to_camel_case(&key)), &camel_case_js_keys(entry.get(1)));
+    }
+    renamed.into()
+}
+
+fn to_js_error(error: &DecodeError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    js_error.set_name("LicenseDecodeError");
+    let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("code"), &JsValue::from_str(error.code()));
+    if let Some(section) = error.section() {
+        let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("section"), &JsValue::from_str(section.id()));
+    }
+    if let Some(field) = error.field() {
+        let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("field"), &JsValue::from_str(field));
+    }
+    if let Some(offset) = error.offset() {
+        let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("offset"), &JsValue::from(offset as u32));
+    }
+    if let Some(rsa_block) = error.rsa_block() {
+        let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("rsa_block"), &JsValue::from(rsa_block as u32));
+    }
+    js_error.into()
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use crate::drivers_license;
+use crate::output;
+use crate::text;
+use crate::vehicle_license;
+
+const LICENSE_LENGTH: usize = 720;
+
+/// The keyboard layout of the host a keyboard-wedge scanner types into. Scanners send US key codes, so on other
+/// layouts some keys produce different characters, e.g. `y` and `z` swap on German keyboards.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    #[default]
+    Us,
+    Uk,
+    German,
+}
+
+impl KeyboardLayout {
+    // Each character a US key code produces on this layout, paired with the character the scanner meant.
+    fn shifted_keys(&self) -> &'static [(char, char)] {
+        match self {
+            KeyboardLayout::Us => &[],
+            KeyboardLayout::Uk => &[('"', '@'), ('@', '"'), ('£', '#'), ('#', '\\'), ('~', '|'), ('¬', '~')],
+            KeyboardLayout::German => &[
+                ('z', 'y'), ('y', 'z'), ('Z', 'Y'), ('Y', 'Z'), ('ß', '-'), ('´', '='), ('ü', '['), ('+', ']'),
+                ('ö', ';'), ('ä', '\''), ('#', '\\'), ('-', '/'), ('^', '`'), ('"', '@'), ('§', '#'), ('&', '^'),
+                ('/', '&'), ('(', '*'), (')', '('), ('=', ')'), ('?', '_'), ('`', '+'), ('Ü', '{'), ('*', '}'),
+                ('Ö', ':'), ('Ä', '"'), ('\'', '|'), ('°', '~'), (';', '<'), (':', '>'), ('_', '?'),
+            ],
+        }
+    }
+
+    fn unshift(&self, typed: char) -> char {
+        self.shifted_keys().iter().find(|(shifted, _)| *shifted == typed).map_or(typed, |(_, intended)| *intended)
+    }
+}
+
+/// The bytes recovered from keyboard-wedge text, and where the recovery is known to be incomplete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct WedgeRepair {
+    #[serde(serialize_with = "output::serialize_bytes")]
+    #[cfg_attr(feature = "wasm", tsify(type = "Uint8Array"))]
+    pub bytes: Vec<u8>,
+    /// Byte positions whose character has no single-byte equivalent. They hold 0x00.
+    pub unrecoverable: Vec<usize>,
+    /// How many bytes short of a 720-byte driver's licence the text is, e.g. from dropped control characters.
+    /// Their positions can't be recovered. Zero for vehicle discs.
+    pub missing_bytes: usize,
+}
+
+/// Reconstructs the scanned bytes from text typed by a keyboard-wedge scanner.
+///
+/// Characters are mapped back through `layout` and then to Latin-1, with Windows-1252 punctuation (e.g. '€')
+/// mapped to its 0x80-0x9F byte. The Enter a scanner appends is dropped unless the text is exactly 720 characters.
+pub fn repair(text: &str, layout: KeyboardLayout) -> WedgeRepair {
+    let text = if text.chars().count() == LICENSE_LENGTH { text } else { text.trim_end_matches(['\r', '\n']) };
+
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut unrecoverable = Vec::new();
+    for typed in text.chars() {
+        let intended = layout.unshift(typed);
+        match u8::try_from(intended).ok().or_else(|| text::windows_1252_byte(intended)) {
+            Some(byte) => bytes.push(byte),
+            None => {
+                unrecoverable.push(bytes.len());
+                bytes.push(0x00);
+            }
+        }
+    }
+
+    // Vehicle discs are '%'-delimited text of no fixed length. Licence ciphertext often contains a '%' byte too, so
+    // only its structure marks the text as a disc.
+    let is_disc = !drivers_license::has_known_header(&bytes) && vehicle_license::looks_like_disc(&bytes);
+    let missing_bytes = if is_disc { 0 } else { LICENSE_LENGTH.saturating_sub(bytes.len()) };
+
+    WedgeRepair { bytes, unrecoverable, missing_bytes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISC: &str = "%MVL1CC14%0154%4025T0JB%1%40250031T0JB%CY123456%ABC123GP%Hatch back / Luikrug%VOLKSWAGEN%POLO%White / Wit%AAVZZZ6RZCU012345%CJZ123456%2025-07-31%";
+
+    // Latin-1 text for bytes, as a wedge scanner on a US layout would type them.
+    fn typed(bytes: &[u8]) -> String {
+        bytes.iter().map(|&b| char::from(b)).collect()
+    }
+
+    #[test]
+    fn truncated_licenses_with_a_percent_byte_report_missing_bytes() {
+        let mut payload: Vec<u8> = [0x01, 0xe1, 0x02, 0x45, 0x00, 0x00].to_vec();
+        payload.extend((0..700).map(|i| (i * 7 % 251) as u8 | 0x40));
+        payload[100] = b'%';
+        let repaired = repair(&typed(&payload), KeyboardLayout::Us);
+        assert_eq!(repaired.bytes, payload);
+        assert_eq!(repaired.missing_bytes, 14);
+    }
+
+    #[test]
+    fn discs_report_no_missing_bytes() {
+        let repaired = repair(&format!("{}\r\n", DISC), KeyboardLayout::Us);
+        assert_eq!(repaired.bytes, DISC.as_bytes());
+        assert_eq!(repaired.missing_bytes, 0);
+        assert!(repaired.unrecoverable.is_empty());
+    }
+
+    #[test]
+    fn german_layouts_swap_y_and_z_back() {
+        assert_eq!(repair("Yaz", KeyboardLayout::German).bytes, b"Zay");
+    }
+}
@@ -0,0 +1,74 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::Value;
+use crate::error::DecodeError;
+use crate::license::{License, SCHEMA_VERSION};
+
+/// Serializes a parsed document as XML, for back-ends that only ingest XML.
+///
+/// The root element is `drivers_license`, `vehicle_license` or `smart_id_card`, with a `schema_version` attribute.
+/// Each field is a child element named after its snake_case key, sorted by name. Lists repeat an `item` element, objects nest their
+/// fields, absent fields are left out, and the photo is base64 text. For example:
+/// `<vehicle_license schema_version="1"><make>VOLKSWAGEN</make>...</vehicle_license>`.
+pub fn to_xml(license: &License) -> Result<String, DecodeError> {
+    let (root, value) = match license {
+        License::Drivers(data) => ("drivers_license", serde_json::to_value(data)),
+        License::Vehicle(data) => ("vehicle_license", serde_json::to_value(data)),
+        License::SmartId(data) => ("smart_id_card", serde_json::to_value(data)),
+    };
+    let mut value = value.map_err(|e| DecodeError::SerializationFailed(e.to_string()))?;
+    if let License::Drivers(data) = license
+        && let Some(image) = &data.image
+    {
+        value["image"] = Value::String(STANDARD.encode(image));
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<{} schema_version=\"{}\">", root, SCHEMA_VERSION));
+    write_children(&mut xml, &value);
+    xml.push_str(&format!("</{}>\n", root));
+    Ok(xml)
+}
+
+fn write_children(xml: &mut String, value: &Value) {
+    match value {
+        Value::Object(object) => {
+            let mut fields: Vec<(&String, &Value)> = object.iter().collect();
+            fields.sort_by_key(|(key, _)| *key);
+            for (key, value) in fields {
+                write_element(xml, key, value);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                write_element(xml, "item", item);
+            }
+        }
+        Value::Null => {}
+        Value::String(text) => escape_into(xml, text),
+        other => xml.push_str(&other.to_string()),
+    }
+}
+
+fn write_element(xml: &mut String, name: &str, value: &Value) {
+    if value.is_null() {
+        return;
+    }
+    xml.push_str(&format!("<{}>", name));
+    write_children(xml, value);
+    xml.push_str(&format!("</{}>", name));
+}
+
+fn escape_into(xml: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            // XML 1.0 doesn't allow other control characters, even escaped.
+            '\t' | '\n' | '\r' => xml.push(c),
+            c if (c as u32) < 0x20 => xml.push(char::REPLACEMENT_CHARACTER),
+            c => xml.push(c),
+        }
+    }
+}